@@ -0,0 +1,429 @@
+use crate::{RpcRequest, RpcResponse, SeqClientError, MAX_RESPONSE_BYTES};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::sync::{mpsc, Mutex};
+
+type PendingResponses =
+    Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Result<RpcResponse, SeqClientError>>>>>;
+
+/// Async counterpart to [`SeqClient`](crate::SeqClient) that multiplexes many
+/// in-flight RPCs over one Unix socket instead of serializing them behind a
+/// mutex.
+///
+/// A background reader task reads newline-framed [`RpcResponse`]s and routes
+/// each to the caller awaiting it, keyed on `request_id`; a background writer
+/// task serializes writes from concurrent callers over the single stream.
+/// This lets callers pipeline, e.g., dozens of clicks/moves without one slow
+/// op (a `screenshot`) blocking the rest.
+pub struct SeqClientAsync {
+    socket_path: PathBuf,
+    writer_tx: mpsc::UnboundedSender<Vec<u8>>,
+    pending: PendingResponses,
+    next_request_id: AtomicU64,
+}
+
+impl SeqClientAsync {
+    pub async fn connect_default() -> Result<Self, SeqClientError> {
+        Self::connect(crate::DEFAULT_SOCKET_PATH).await
+    }
+
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, SeqClientError> {
+        let stream = UnixStream::connect(path.as_ref()).await?;
+        let (read_half, write_half) = stream.into_split();
+        let pending: PendingResponses = Arc::new(Mutex::new(HashMap::new()));
+
+        let (writer_tx, writer_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        tokio::spawn(write_loop(write_half, writer_rx));
+        tokio::spawn(read_loop(read_half, pending.clone()));
+
+        Ok(Self {
+            socket_path: path.as_ref().to_path_buf(),
+            writer_tx,
+            pending,
+            next_request_id: AtomicU64::new(1),
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Sends `request` and awaits its matching response. Assigns a fresh
+    /// `request_id` if the caller didn't set one, since correlation depends
+    /// on it being unique among in-flight calls. Built on [`Self::call_stream`],
+    /// taking only the first frame: against a streaming op it returns that
+    /// op's first (earliest) frame, and every later frame is discarded.
+    pub async fn call(&self, request: RpcRequest) -> Result<RpcResponse, SeqClientError> {
+        let mut stream = self.call_stream(request).await?;
+        stream.next().await.ok_or_else(|| {
+            SeqClientError::Protocol("connection closed before any response".to_string())
+        })?
+    }
+
+    /// Like [`Self::call`], but for ops that reply with a sequence of frames
+    /// (`run_macro` progress, a future `watch` op) rather than exactly one.
+    /// Keeps the `request_id` registered with the background reader task
+    /// until a terminal frame arrives (see [`RpcResponse::is_terminal`]),
+    /// yielding each frame from the returned stream in order.
+    pub async fn call_stream(&self, mut request: RpcRequest) -> Result<CallStreamAsync, SeqClientError> {
+        let request_id = request
+            .request_id
+            .clone()
+            .unwrap_or_else(|| self.generate_request_id());
+        request.request_id = Some(request_id.clone());
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.pending.lock().await.insert(request_id.clone(), tx);
+
+        let mut payload = serde_json::to_vec(&request)?;
+        payload.push(b'\n');
+        if self.writer_tx.send(payload).is_err() {
+            self.pending.lock().await.remove(&request_id);
+            return Err(SeqClientError::Protocol(
+                "writer task has shut down".to_string(),
+            ));
+        }
+
+        Ok(CallStreamAsync { rx, done: false })
+    }
+
+    pub async fn call_ok(&self, request: RpcRequest) -> Result<Value, SeqClientError> {
+        let response = self.call(request).await?;
+        if response.ok {
+            Ok(response.result.unwrap_or_else(|| json!({})))
+        } else {
+            Err(SeqClientError::Remote(
+                response.error.unwrap_or_else(|| "unknown_error".to_string()),
+            ))
+        }
+    }
+
+    fn generate_request_id(&self) -> String {
+        let n = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        format!("seq-async-{n}")
+    }
+}
+
+/// Stream returned by [`SeqClientAsync::call_stream`]. Yields each frame the
+/// daemon sends for a `request_id` in order, stopping after the terminal one
+/// or once the connection is gone.
+pub struct CallStreamAsync {
+    rx: mpsc::UnboundedReceiver<Result<RpcResponse, SeqClientError>>,
+    done: bool,
+}
+
+impl CallStreamAsync {
+    pub async fn next(&mut self) -> Option<Result<RpcResponse, SeqClientError>> {
+        if self.done {
+            return None;
+        }
+        match self.rx.recv().await {
+            Some(Ok(response)) => {
+                self.done = response.is_terminal();
+                Some(Ok(response))
+            }
+            Some(Err(err)) => {
+                self.done = true;
+                Some(Err(err))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+async fn write_loop(mut write_half: OwnedWriteHalf, mut writer_rx: mpsc::UnboundedReceiver<Vec<u8>>) {
+    while let Some(payload) = writer_rx.recv().await {
+        if write_half.write_all(&payload).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Reads newline-framed [`RpcResponse`]s until EOF or a read/parse error,
+/// forwarding each to its waiter. A frame only removes its `request_id` from
+/// `pending` once it's terminal (see [`RpcResponse::is_terminal`]), so a
+/// streaming op can keep delivering progress frames to the same waiter
+/// across multiple iterations of this loop. On exit, drains whatever is left
+/// in `pending` and fails every one of those waiters with the same
+/// [`SeqClientError::Protocol`] describing why the connection ended.
+async fn read_loop(read_half: OwnedReadHalf, pending: PendingResponses) {
+    let mut reader = BufReader::new(read_half);
+    let failure_reason = loop {
+        match read_frame(&mut reader).await {
+            Ok(Some(line)) => {
+                if line.is_empty() {
+                    continue;
+                }
+                match serde_json::from_slice::<RpcResponse>(&line) {
+                    Ok(response) => {
+                        let mut pending = pending.lock().await;
+                        if response.is_terminal() {
+                            if let Some(sender) = pending.remove(&response.request_id) {
+                                let _ = sender.send(Ok(response));
+                            }
+                        } else if let Some(sender) = pending.get(&response.request_id) {
+                            let _ = sender.send(Ok(response));
+                        }
+                    }
+                    Err(err) => break format!("malformed response line: {err}"),
+                }
+            }
+            Ok(None) => break "connection closed by seqd".to_string(),
+            Err(err) => break err.to_string(),
+        }
+    };
+
+    for (_, sender) in pending.lock().await.drain() {
+        let _ = sender.send(Err(SeqClientError::Protocol(failure_reason.clone())));
+    }
+}
+
+/// Reads one newline-terminated frame off `reader`, a [`BufReader`] kept
+/// alive across calls so the unconsumed tail of one `read` — several
+/// pipelined frames arriving in the same packet — carries over to the next
+/// call instead of being dropped. Enforces [`MAX_RESPONSE_BYTES`] per frame,
+/// same as the sync client's `read_response_line`. Returns `Ok(None)` on a
+/// clean EOF with no partial frame buffered.
+async fn read_frame(
+    reader: &mut BufReader<OwnedReadHalf>,
+) -> Result<Option<Vec<u8>>, SeqClientError> {
+    let mut buf = Vec::with_capacity(512);
+    let n = reader.read_until(b'\n', &mut buf).await?;
+    if n == 0 {
+        return Ok(None);
+    }
+    if buf.last() == Some(&b'\n') {
+        buf.pop();
+    }
+    if buf.len() > MAX_RESPONSE_BYTES {
+        return Err(SeqClientError::Protocol(
+            "response exceeded max size".to_string(),
+        ));
+    }
+    Ok(Some(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+    use tokio::net::UnixListener;
+
+    fn test_socket_path(tag: &str) -> PathBuf {
+        let mut p = std::env::temp_dir();
+        let pid = std::process::id();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("clock")
+            .as_nanos();
+        p.push(format!("seq_client_async_{tag}_{pid}_{now}.sock"));
+        p
+    }
+
+    #[tokio::test]
+    async fn correlates_out_of_order_responses_by_request_id() {
+        let path = test_socket_path("ooo");
+        let listener = UnixListener::bind(&path).expect("bind");
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let (read_half, mut write_half) = stream.into_split();
+            let mut lines = BufReader::new(read_half).lines();
+
+            let mut seen = Vec::new();
+            for _ in 0..2 {
+                let line = lines.next_line().await.expect("read").expect("line");
+                let req: Value = serde_json::from_str(&line).expect("parse req");
+                seen.push(req["request_id"].as_str().unwrap().to_string());
+            }
+
+            // Reply in reverse order to prove responses aren't matched by
+            // write order.
+            for request_id in seen.into_iter().rev() {
+                let response = json!({
+                    "ok": true,
+                    "op": "ping",
+                    "request_id": request_id,
+                    "run_id": "",
+                    "tool_call_id": "",
+                    "ts_ms": 1,
+                    "dur_us": 2,
+                    "result": { "request_id": request_id }
+                });
+                write_half
+                    .write_all(format!("{response}\n").as_bytes())
+                    .await
+                    .expect("write");
+            }
+        });
+
+        let client = SeqClientAsync::connect(&path).await.expect("connect");
+        let (first, second) = tokio::join!(
+            client.call(RpcRequest::new("ping").with_request_id("first")),
+            client.call(RpcRequest::new("ping").with_request_id("second")),
+        );
+
+        let first = first.expect("first call");
+        let second = second.expect("second call");
+        assert_eq!(first.result.unwrap()["request_id"], "first");
+        assert_eq!(second.result.unwrap()["request_id"], "second");
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn eof_fails_pending_calls_with_protocol_error() {
+        let path = test_socket_path("eof");
+        let listener = UnixListener::bind(&path).expect("bind");
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let (mut read_half, _write_half) = stream.into_split();
+            // Drain the request then close without ever responding.
+            let mut buf = [0u8; 256];
+            let _ = read_half.read(&mut buf).await;
+        });
+
+        let client = SeqClientAsync::connect(&path).await.expect("connect");
+        let err = client
+            .call(RpcRequest::new("ping"))
+            .await
+            .expect_err("should fail once connection closes");
+        match err {
+            SeqClientError::Protocol(_) => {}
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn call_stream_yields_frames_until_terminal() {
+        let path = test_socket_path("stream");
+        let listener = UnixListener::bind(&path).expect("bind");
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let (read_half, mut write_half) = stream.into_split();
+            let mut lines = BufReader::new(read_half).lines();
+            let line = lines.next_line().await.expect("read").expect("line");
+            let req: Value = serde_json::from_str(&line).expect("parse req");
+            let request_id = req["request_id"].as_str().unwrap().to_string();
+
+            for step in 0..3 {
+                let response = json!({
+                    "ok": true,
+                    "op": "run_macro",
+                    "request_id": request_id,
+                    "run_id": "",
+                    "tool_call_id": "",
+                    "ts_ms": step,
+                    "dur_us": 1,
+                    "result": { "step": step },
+                    "stream": true
+                });
+                write_half
+                    .write_all(format!("{response}\n").as_bytes())
+                    .await
+                    .expect("write progress frame");
+            }
+            let done = json!({
+                "ok": true,
+                "op": "run_macro",
+                "request_id": request_id,
+                "run_id": "",
+                "tool_call_id": "",
+                "ts_ms": 3,
+                "dur_us": 1,
+                "result": { "step": 3, "finished": true },
+                "stream": false
+            });
+            write_half
+                .write_all(format!("{done}\n").as_bytes())
+                .await
+                .expect("write final frame");
+        });
+
+        let client = SeqClientAsync::connect(&path).await.expect("connect");
+        let mut stream = client
+            .call_stream(RpcRequest::new("run_macro"))
+            .await
+            .expect("call_stream");
+
+        let mut frames = Vec::new();
+        while let Some(frame) = stream.next().await {
+            frames.push(frame.expect("frame ok"));
+        }
+
+        assert_eq!(frames.len(), 4);
+        assert!(frames[..3].iter().all(|f| f.stream));
+        assert!(!frames[3].stream);
+        assert_eq!(frames[3].result.as_ref().unwrap()["finished"], true);
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn read_loop_handles_multiple_frames_in_one_read() {
+        let path = test_socket_path("multiframe");
+        let listener = UnixListener::bind(&path).expect("bind");
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            let (read_half, mut write_half) = stream.into_split();
+            let mut lines = BufReader::new(read_half).lines();
+
+            let mut seen = Vec::new();
+            for _ in 0..2 {
+                let line = lines.next_line().await.expect("read").expect("line");
+                let req: Value = serde_json::from_str(&line).expect("parse req");
+                seen.push(req["request_id"].as_str().unwrap().to_string());
+            }
+
+            // Write both responses in a single `write_all` so the client's
+            // reader sees two frames arrive in one `read()`.
+            let mut batched = String::new();
+            for request_id in &seen {
+                let response = json!({
+                    "ok": true,
+                    "op": "ping",
+                    "request_id": request_id,
+                    "run_id": "",
+                    "tool_call_id": "",
+                    "ts_ms": 1,
+                    "dur_us": 2,
+                    "result": { "request_id": request_id }
+                });
+                batched.push_str(&response.to_string());
+                batched.push('\n');
+            }
+            write_half
+                .write_all(batched.as_bytes())
+                .await
+                .expect("write batched responses");
+        });
+
+        let client = SeqClientAsync::connect(&path).await.expect("connect");
+        let (first, second) = tokio::join!(
+            client.call(RpcRequest::new("ping").with_request_id("first")),
+            client.call(RpcRequest::new("ping").with_request_id("second")),
+        );
+
+        let first = first.expect("first call");
+        let second = second.expect("second call");
+        assert_eq!(first.result.unwrap()["request_id"], "first");
+        assert_eq!(second.result.unwrap()["request_id"], "second");
+
+        let _ = fs::remove_file(path);
+    }
+}
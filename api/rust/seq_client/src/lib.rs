@@ -1,12 +1,20 @@
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::io::{Read, Write};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
 use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 use std::time::Duration;
 use thiserror::Error;
 
+pub mod async_client;
+
+pub use async_client::SeqClientAsync;
+
 const DEFAULT_SOCKET_PATH: &str = "/tmp/seqd.sock";
 const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
 
@@ -20,6 +28,8 @@ pub enum SeqClientError {
     Protocol(String),
     #[error("remote error: {0}")]
     Remote(String),
+    #[error("disconnected: {0}")]
+    Disconnected(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -33,6 +43,12 @@ pub struct RpcRequest {
     pub tool_call_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub args: Option<Value>,
+    /// Marks this request safe to resend on a reconnected connection, e.g.
+    /// after [`SeqClient::connect_resilient`] re-establishes the socket
+    /// following a daemon restart. Never sent over the wire — purely local
+    /// bookkeeping for the reconnect supervisor.
+    #[serde(skip)]
+    pub idempotent: bool,
 }
 
 impl RpcRequest {
@@ -67,6 +83,15 @@ impl RpcRequest {
         self.args = Some(serde_json::to_value(args)?);
         Ok(self)
     }
+
+    /// Flags this request as safe for [`SeqClient::connect_resilient`] to
+    /// resend verbatim (same `request_id`) on a fresh connection after a
+    /// reconnect, rather than failing it with [`SeqClientError::Disconnected`]
+    /// like a non-idempotent call in flight during the outage.
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,14 +110,220 @@ pub struct RpcResponse {
     pub result: Option<Value>,
     #[serde(default)]
     pub error: Option<String>,
+    /// `true` if more frames for this `request_id` are still coming, as with
+    /// a `run_macro` reporting step-by-step progress. A plain one-shot op
+    /// never sets this, so it defaults to `false` and [`SeqClient::call`]
+    /// behaves exactly as before.
+    #[serde(default)]
+    pub stream: bool,
 }
 
-#[derive(Debug)]
-pub struct SeqClient {
+impl RpcResponse {
+    /// A frame is terminal once there's nothing left to wait for: either the
+    /// sender said so (`stream: false`) or the op failed outright (`ok:
+    /// false`), per the kuska-ssb `RecvMsg` convention this protocol follows.
+    fn is_terminal(&self) -> bool {
+        !self.stream || !self.ok
+    }
+}
+
+/// An unsolicited, server-pushed frame (window focus changed, an app
+/// launched, a macro finished, ...) delivered outside the request/response
+/// cycle to anyone who called [`SeqClient::subscribe`]. Distinguished on the
+/// wire from an [`RpcResponse`] by a `"type":"event"` tag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcEvent {
+    pub event: String,
+    #[serde(default)]
+    pub ts_ms: u64,
+    #[serde(default)]
+    pub body: Value,
+}
+
+/// One in-flight [`SeqClient::call`]/[`SeqClient::call_stream`], tracked by
+/// `request_id` so a reconnect supervisor can tell a resendable
+/// [`RpcRequest::idempotent`] call apart from one that must fail outright,
+/// and replay the former's exact request once a fresh connection is up.
+struct PendingCall {
+    request: RpcRequest,
+    sender: mpsc::Sender<Result<RpcResponse, SeqClientError>>,
+}
+
+type PendingResponses = Arc<Mutex<HashMap<String, PendingCall>>>;
+type EventSubscribers = Arc<Mutex<Vec<mpsc::Sender<RpcEvent>>>>;
+
+/// Iterator returned by [`SeqClient::call_stream`]. Yields each frame the
+/// daemon sends for a `request_id` in order, stopping after the terminal one
+/// (see [`RpcResponse::is_terminal`]) or once the connection is gone.
+pub struct CallStream {
+    rx: mpsc::Receiver<Result<RpcResponse, SeqClientError>>,
+    done: bool,
+}
+
+impl Iterator for CallStream {
+    type Item = Result<RpcResponse, SeqClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.rx.recv() {
+            Ok(Ok(response)) => {
+                self.done = response.is_terminal();
+                Some(Ok(response))
+            }
+            Ok(Err(err)) => {
+                self.done = true;
+                Some(Err(err))
+            }
+            Err(_) => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Abstracts the newline-framed JSON protocol over whatever byte stream
+/// connects to `seqd`, so [`SeqClient`] isn't hardcoded to [`UnixStream`].
+/// Mirrors the DAP client pattern of picking a transport (`tcp` vs `stdio`)
+/// behind one interface: `write_request`/`read_response_line` operate
+/// against `dyn Transport` so both transports share one code path.
+pub trait Transport: Read + Write + Send {
+    /// Clones the underlying stream so the background reader thread can own
+    /// its half while the writer keeps the other, same as `UnixStream`'s
+    /// `try_clone`.
+    fn try_clone_box(&self) -> std::io::Result<Box<dyn Transport>>;
+}
+
+impl Transport for UnixStream {
+    fn try_clone_box(&self) -> std::io::Result<Box<dyn Transport>> {
+        Ok(Box::new(self.try_clone()?))
+    }
+}
+
+impl Transport for TcpStream {
+    fn try_clone_box(&self) -> std::io::Result<Box<dyn Transport>> {
+        Ok(Box::new(self.try_clone()?))
+    }
+}
+
+/// Where a [`SeqClient`] is connected: the default local Unix socket, or a
+/// TCP address for a daemon running on another machine or forwarded out of
+/// a container.
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    Unix(PathBuf),
+    Tcp(SocketAddr),
+}
+
+/// Backoff schedule for [`SeqClient::connect_resilient`]'s reconnect
+/// supervisor: capped exponential backoff between attempts to re-establish
+/// the Unix socket, the same shape as the ethers-rs IPC transport's
+/// reconnect loop. `max_attempts` bounds a single outage's retries; `None`
+/// means keep trying forever.
+#[derive(Debug, Clone)]
+pub struct ReconnectConfig {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            max_attempts: None,
+        }
+    }
+}
+
+/// The live Unix socket behind a [`SeqClient::connect_resilient`]
+/// connection, shared between the writer half and the background
+/// supervisor so a reconnect is visible to both sides as soon as it
+/// happens.
+struct ResilientSocket {
     socket_path: PathBuf,
+    config: ReconnectConfig,
     stream: Mutex<UnixStream>,
 }
 
+impl ResilientSocket {
+    /// Blocks, retrying with backoff, until a new connection to
+    /// `socket_path` succeeds or `config.max_attempts` is exhausted.
+    fn reconnect(&self) -> Result<UnixStream, SeqClientError> {
+        let mut backoff = self.config.initial_backoff;
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            match UnixStream::connect(&self.socket_path) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => {
+                    if let Some(max) = self.config.max_attempts {
+                        if attempt >= max {
+                            return Err(SeqClientError::Disconnected(format!(
+                                "failed to reconnect to {} after {attempt} attempt(s): {err}",
+                                self.socket_path.display()
+                            )));
+                        }
+                    }
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.config.max_backoff);
+                }
+            }
+        }
+    }
+}
+
+/// [`Transport`] for a [`SeqClient::connect_resilient`] connection: every
+/// read/write goes through the shared [`ResilientSocket`], so once the
+/// supervisor thread swaps in a reconnected stream, the very next call sees
+/// it automatically.
+struct ResilientWriter(Arc<ResilientSocket>);
+
+impl Read for ResilientWriter {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0
+            .stream
+            .lock()
+            .expect("resilient socket mutex poisoned")
+            .read(buf)
+    }
+}
+
+impl Write for ResilientWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .stream
+            .lock()
+            .expect("resilient socket mutex poisoned")
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0
+            .stream
+            .lock()
+            .expect("resilient socket mutex poisoned")
+            .flush()
+    }
+}
+
+impl Transport for ResilientWriter {
+    fn try_clone_box(&self) -> std::io::Result<Box<dyn Transport>> {
+        Ok(Box::new(ResilientWriter(self.0.clone())))
+    }
+}
+
+pub struct SeqClient {
+    endpoint: Endpoint,
+    writer: Mutex<Box<dyn Transport>>,
+    pending: PendingResponses,
+    subscribers: EventSubscribers,
+    next_request_id: AtomicU64,
+}
+
 impl SeqClient {
     pub fn connect_default() -> Result<Self, SeqClientError> {
         Self::connect(DEFAULT_SOCKET_PATH)
@@ -100,10 +331,7 @@ impl SeqClient {
 
     pub fn connect(path: impl AsRef<Path>) -> Result<Self, SeqClientError> {
         let stream = UnixStream::connect(path.as_ref())?;
-        Ok(Self {
-            socket_path: path.as_ref().to_path_buf(),
-            stream: Mutex::new(stream),
-        })
+        Self::from_stream(Endpoint::Unix(path.as_ref().to_path_buf()), Box::new(stream))
     }
 
     pub fn connect_with_timeout(
@@ -113,25 +341,172 @@ impl SeqClient {
         let stream = UnixStream::connect(path.as_ref())?;
         stream.set_read_timeout(Some(timeout))?;
         stream.set_write_timeout(Some(timeout))?;
-        Ok(Self {
-            socket_path: path.as_ref().to_path_buf(),
+        Self::from_stream(Endpoint::Unix(path.as_ref().to_path_buf()), Box::new(stream))
+    }
+
+    /// Connects to a `seqd` listening on a TCP address instead of the usual
+    /// Unix socket, e.g. a daemon on another machine or forwarded out of a
+    /// container. Speaks the exact same newline-framed JSON protocol.
+    pub fn connect_tcp(addr: SocketAddr) -> Result<Self, SeqClientError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Self::from_stream(Endpoint::Tcp(addr), Box::new(stream))
+    }
+
+    /// Connects to the Unix socket at `path`, same as [`SeqClient::connect`],
+    /// but survives the daemon bouncing: a background supervisor detects a
+    /// read/write error or EOF, reconnects with `config`'s backoff, replays
+    /// any in-flight call marked [`RpcRequest::idempotent`] on the fresh
+    /// connection, and fails the rest with [`SeqClientError::Disconnected`].
+    /// Callers never need to rebuild the client after a restart.
+    pub fn connect_resilient(
+        path: impl AsRef<Path>,
+        config: ReconnectConfig,
+    ) -> Result<Self, SeqClientError> {
+        let socket_path = path.as_ref().to_path_buf();
+        let stream = UnixStream::connect(&socket_path)?;
+        let socket = Arc::new(ResilientSocket {
+            socket_path: socket_path.clone(),
+            config,
             stream: Mutex::new(stream),
+        });
+
+        let pending: PendingResponses = Arc::new(Mutex::new(HashMap::new()));
+        let subscribers: EventSubscribers = Arc::new(Mutex::new(Vec::new()));
+
+        let reader_socket = socket.clone();
+        let reader_pending = pending.clone();
+        let reader_subscribers = subscribers.clone();
+        thread::spawn(move || resilient_read_loop(reader_socket, reader_pending, reader_subscribers));
+
+        Ok(Self {
+            endpoint: Endpoint::Unix(socket_path),
+            writer: Mutex::new(Box::new(ResilientWriter(socket))),
+            pending,
+            subscribers,
+            next_request_id: AtomicU64::new(1),
+        })
+    }
+
+    fn from_stream(endpoint: Endpoint, stream: Box<dyn Transport>) -> Result<Self, SeqClientError> {
+        let reader_stream = stream.try_clone_box()?;
+        let pending: PendingResponses = Arc::new(Mutex::new(HashMap::new()));
+        let subscribers: EventSubscribers = Arc::new(Mutex::new(Vec::new()));
+
+        let reader_pending = pending.clone();
+        let reader_subscribers = subscribers.clone();
+        thread::spawn(move || read_loop(reader_stream, reader_pending, reader_subscribers));
+
+        Ok(Self {
+            endpoint,
+            writer: Mutex::new(stream),
+            pending,
+            subscribers,
+            next_request_id: AtomicU64::new(1),
         })
     }
 
-    pub fn socket_path(&self) -> &Path {
-        &self.socket_path
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
     }
 
-    pub fn call(&self, request: RpcRequest) -> Result<RpcResponse, SeqClientError> {
+    /// Sends `request` and blocks for its matching response, correlated on
+    /// `request_id` by the background reader thread. Assigns a fresh
+    /// `request_id` if the caller didn't set one.
+    pub fn call(&self, mut request: RpcRequest) -> Result<RpcResponse, SeqClientError> {
+        let request_id = request
+            .request_id
+            .clone()
+            .unwrap_or_else(|| self.generate_request_id());
+        request.request_id = Some(request_id.clone());
+
+        let (tx, rx) = mpsc::channel();
+        self.pending
+            .lock()
+            .map_err(|_| SeqClientError::Protocol("pending map mutex poisoned".into()))?
+            .insert(
+                request_id.clone(),
+                PendingCall {
+                    request: request.clone(),
+                    sender: tx,
+                },
+            );
+
+        if let Err(err) = self.write_request(&request) {
+            self.pending
+                .lock()
+                .map_err(|_| SeqClientError::Protocol("pending map mutex poisoned".into()))?
+                .remove(&request_id);
+            return Err(err);
+        }
+
+        rx.recv().map_err(|_| {
+            SeqClientError::Protocol(format!(
+                "connection closed while awaiting response to request_id {request_id}"
+            ))
+        })?
+    }
+
+    /// Like [`SeqClient::call`], but for ops that reply with a sequence of
+    /// frames (`run_macro` progress, a future `watch` op) rather than
+    /// exactly one. Keeps the `request_id` registered with the background
+    /// reader thread until a terminal frame arrives, yielding each frame to
+    /// the returned iterator in order.
+    pub fn call_stream(&self, mut request: RpcRequest) -> Result<CallStream, SeqClientError> {
+        let request_id = request
+            .request_id
+            .clone()
+            .unwrap_or_else(|| self.generate_request_id());
+        request.request_id = Some(request_id.clone());
+
+        let (tx, rx) = mpsc::channel();
+        self.pending
+            .lock()
+            .map_err(|_| SeqClientError::Protocol("pending map mutex poisoned".into()))?
+            .insert(
+                request_id.clone(),
+                PendingCall {
+                    request: request.clone(),
+                    sender: tx,
+                },
+            );
+
+        if let Err(err) = self.write_request(&request) {
+            self.pending
+                .lock()
+                .map_err(|_| SeqClientError::Protocol("pending map mutex poisoned".into()))?
+                .remove(&request_id);
+            return Err(err);
+        }
+
+        Ok(CallStream { rx, done: false })
+    }
+
+    /// Registers interest in server-pushed [`RpcEvent`]s and tells the
+    /// daemon (via an `op: "subscribe"` request) to start streaming them.
+    /// Every call returns its own receiver; the background reader thread
+    /// fans each incoming event out to all of them.
+    pub fn subscribe(&self) -> Result<mpsc::Receiver<RpcEvent>, SeqClientError> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .map_err(|_| SeqClientError::Protocol("subscriber list mutex poisoned".into()))?
+            .push(tx);
+        self.call_ok(RpcRequest::new("subscribe"))?;
+        Ok(rx)
+    }
+
+    fn write_request(&self, request: &RpcRequest) -> Result<(), SeqClientError> {
         let mut stream = self
-            .stream
+            .writer
             .lock()
             .map_err(|_| SeqClientError::Protocol("socket mutex poisoned".into()))?;
-        write_request(&mut stream, &request)?;
-        let line = read_response_line(&mut stream)?;
-        let response: RpcResponse = serde_json::from_slice(&line)?;
-        Ok(response)
+        write_request(&mut **stream, request)
+    }
+
+    fn generate_request_id(&self) -> String {
+        let n = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        format!("seq-{n}")
     }
 
     pub fn call_ok(&self, request: RpcRequest) -> Result<Value, SeqClientError> {
@@ -145,6 +520,50 @@ impl SeqClient {
         }
     }
 
+    /// Fires `requests` over the multiplexed connection with at most
+    /// `max_in_flight` calls in progress at once, inspired by a bounded
+    /// worker-pool: each worker pulls the next request and calls it, and one
+    /// request's remote/protocol error doesn't abort the rest. The returned
+    /// `Vec` lines up with `requests` by index regardless of the order
+    /// workers finish in, so callers can zip results back against their
+    /// inputs. `max_in_flight` is clamped to at least 1 and at most
+    /// `requests.len()`.
+    pub fn call_batch(
+        &self,
+        requests: Vec<RpcRequest>,
+        max_in_flight: usize,
+    ) -> Vec<Result<RpcResponse, SeqClientError>> {
+        if requests.is_empty() {
+            return Vec::new();
+        }
+        let worker_count = max_in_flight.max(1).min(requests.len());
+        let next_index = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<Result<RpcResponse, SeqClientError>>>> =
+            Mutex::new((0..requests.len()).map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..worker_count {
+                scope.spawn(|| loop {
+                    let idx = next_index.fetch_add(1, Ordering::Relaxed);
+                    if idx >= requests.len() {
+                        break;
+                    }
+                    let outcome = self.call(requests[idx].clone());
+                    results
+                        .lock()
+                        .expect("call_batch results mutex poisoned")[idx] = Some(outcome);
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .expect("call_batch results mutex poisoned")
+            .into_iter()
+            .map(|slot| slot.expect("every index is assigned to exactly one worker"))
+            .collect()
+    }
+
     pub fn ping(&self) -> Result<RpcResponse, SeqClientError> {
         self.call(RpcRequest::new("ping"))
     }
@@ -210,34 +629,60 @@ impl SeqClient {
         };
         self.call(req)
     }
+
+    pub fn type_text(&self, text: &str, wpm: Option<f64>) -> Result<RpcResponse, SeqClientError> {
+        let mut args = json!({ "text": text });
+        if let Some(wpm) = wpm {
+            args["wpm"] = json!(wpm);
+        }
+        self.call(RpcRequest::new("type_text").with_args_json(args))
+    }
+
+    pub fn key_press(&self, key: &str) -> Result<RpcResponse, SeqClientError> {
+        self.call(RpcRequest::new("key_press").with_args_json(json!({ "key": key })))
+    }
+
+    pub fn key_combo(&self, mods: &[&str], key: &str) -> Result<RpcResponse, SeqClientError> {
+        self.call(RpcRequest::new("key_combo").with_args_json(json!({ "mods": mods, "key": key })))
+    }
 }
 
-fn write_request(stream: &mut UnixStream, request: &RpcRequest) -> Result<(), SeqClientError> {
+fn write_request(stream: &mut dyn Transport, request: &RpcRequest) -> Result<(), SeqClientError> {
     let mut payload = serde_json::to_vec(request)?;
     payload.push(b'\n');
     stream.write_all(&payload)?;
     Ok(())
 }
 
-fn read_response_line(stream: &mut UnixStream) -> Result<Vec<u8>, SeqClientError> {
+/// Reads one newline-terminated frame from `reader`. `reader` must be a
+/// [`BufReader`] kept alive across calls: with pipelining (several
+/// multiplexed calls in flight, or [`SeqClient::call_stream`]'s progress
+/// frames) one underlying `read` can return more than one frame at once, and
+/// `BufReader` carries the unconsumed tail of that read over to the next
+/// call instead of it being silently dropped.
+fn read_response_line<R: BufRead>(reader: &mut R) -> Result<Vec<u8>, SeqClientError> {
     let mut out = Vec::with_capacity(512);
-    let mut buf = [0u8; 512];
     loop {
-        let n = stream.read(&mut buf)?;
-        if n == 0 {
+        let available = reader.fill_buf()?;
+        if available.is_empty() {
             if out.is_empty() {
                 return Err(SeqClientError::Protocol(
                     "unexpected EOF while waiting for response".to_string(),
                 ));
             }
-            break;
+            return Ok(out);
         }
-        for b in &buf[..n] {
-            out.push(*b);
-            if *b == b'\n' {
-                out.pop();
+        match available.iter().position(|&b| b == b'\n') {
+            Some(pos) => {
+                out.extend_from_slice(&available[..pos]);
+                reader.consume(pos + 1);
                 return Ok(out);
             }
+            None => {
+                let consumed = available.len();
+                out.extend_from_slice(available);
+                reader.consume(consumed);
+            }
         }
         if out.len() > MAX_RESPONSE_BYTES {
             return Err(SeqClientError::Protocol(
@@ -245,7 +690,165 @@ fn read_response_line(stream: &mut UnixStream) -> Result<Vec<u8>, SeqClientError
             ));
         }
     }
-    Ok(out)
+}
+
+/// Reads newline-framed wire frames off `stream` until it errors or hits
+/// EOF, routing each one to whichever half of the protocol it belongs to: a
+/// `"type":"event"` frame fans out to every subscriber in `subscribers`,
+/// anything else is parsed as an [`RpcResponse`] and delivered to the
+/// matching entry in `pending`. On exit, drains `pending` and fails every
+/// remaining waiter with the reason the loop stopped.
+fn read_loop(stream: Box<dyn Transport>, pending: PendingResponses, subscribers: EventSubscribers) {
+    let mut reader = BufReader::new(stream);
+    let failure_reason = loop {
+        let line = match read_response_line(&mut reader) {
+            Ok(line) => line,
+            Err(err) => break err.to_string(),
+        };
+        if line.is_empty() {
+            continue;
+        }
+
+        let value: Value = match serde_json::from_slice(&line) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        if value.get("type").and_then(Value::as_str) == Some("event") {
+            dispatch_event(&subscribers, value);
+        } else {
+            dispatch_response(&pending, value);
+        }
+    };
+
+    if let Ok(mut pending) = pending.lock() {
+        for (_, call) in pending.drain() {
+            let _ = call.sender.send(Err(SeqClientError::Protocol(failure_reason.clone())));
+        }
+    }
+}
+
+/// Background supervisor for a [`SeqClient::connect_resilient`] connection.
+/// Behaves like [`read_loop`] while the socket is healthy, but on a read
+/// error or EOF hands off to [`recover_connection`] instead of giving up,
+/// then resumes reading from whatever stream that returns.
+fn resilient_read_loop(socket: Arc<ResilientSocket>, pending: PendingResponses, subscribers: EventSubscribers) {
+    let mut reader = match socket.stream.lock() {
+        Ok(stream) => match stream.try_clone() {
+            Ok(clone) => BufReader::new(clone),
+            Err(_) => return,
+        },
+        Err(_) => return,
+    };
+
+    loop {
+        let line = match read_response_line(&mut reader) {
+            Ok(line) => line,
+            Err(_) => match recover_connection(&socket, &pending) {
+                Some(new_reader) => {
+                    reader = BufReader::new(new_reader);
+                    continue;
+                }
+                None => break,
+            },
+        };
+        if line.is_empty() {
+            continue;
+        }
+
+        let value: Value = match serde_json::from_slice(&line) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        if value.get("type").and_then(Value::as_str) == Some("event") {
+            dispatch_event(&subscribers, value);
+        } else {
+            dispatch_response(&pending, value);
+        }
+    }
+}
+
+/// Fails every non-idempotent pending call with
+/// [`SeqClientError::Disconnected`], reconnects `socket`, and resends the
+/// idempotent ones (same `request_id`, so their original caller still gets
+/// the eventual response) on the new connection. Returns a reader clone of
+/// the new connection on success. Returns `None` once reconnecting itself
+/// gives up — at which point every remaining pending call, idempotent or
+/// not, is also failed with [`SeqClientError::Disconnected`].
+///
+/// Holds `pending`'s lock for the whole reconnect attempt, including the
+/// backoff sleeps inside [`ResilientSocket::reconnect`]: [`SeqClient::call`]
+/// and [`SeqClient::call_stream`] take that same lock before writing, so a
+/// call made mid-outage simply waits for recovery to finish instead of
+/// writing through to the stale stream and going unresent/unfailed.
+fn recover_connection(socket: &Arc<ResilientSocket>, pending: &PendingResponses) -> Option<UnixStream> {
+    let mut pending_guard = pending.lock().ok()?;
+
+    let stale: Vec<String> = pending_guard
+        .iter()
+        .filter(|(_, call)| !call.request.idempotent)
+        .map(|(request_id, _)| request_id.clone())
+        .collect();
+    for request_id in stale {
+        if let Some(call) = pending_guard.remove(&request_id) {
+            let _ = call.sender.send(Err(SeqClientError::Disconnected(
+                "connection to seqd was lost".to_string(),
+            )));
+        }
+    }
+
+    match socket.reconnect() {
+        Ok(new_stream) => {
+            let reader_clone = new_stream.try_clone().ok()?;
+            if let Ok(mut guard) = socket.stream.lock() {
+                *guard = new_stream;
+                for call in pending_guard.values() {
+                    let _ = write_request(&mut *guard, &call.request);
+                }
+            }
+            Some(reader_clone)
+        }
+        Err(reason) => {
+            for (_, call) in pending_guard.drain() {
+                let _ = call
+                    .sender
+                    .send(Err(SeqClientError::Disconnected(reason.to_string())));
+            }
+            None
+        }
+    }
+}
+
+fn dispatch_event(subscribers: &EventSubscribers, value: Value) {
+    let Ok(event) = serde_json::from_value::<RpcEvent>(value) else {
+        return;
+    };
+    if let Ok(subs) = subscribers.lock() {
+        for tx in subs.iter() {
+            let _ = tx.send(event.clone());
+        }
+    }
+}
+
+/// Forwards `value` to the pending entry for its `request_id`. The entry is
+/// only removed once the frame is terminal, so a streaming op (`stream:
+/// true`) can keep delivering frames to the same caller across multiple
+/// calls to this function.
+fn dispatch_response(pending: &PendingResponses, value: Value) {
+    let Ok(response) = serde_json::from_value::<RpcResponse>(value) else {
+        return;
+    };
+    let Ok(mut pending) = pending.lock() else {
+        return;
+    };
+    if response.is_terminal() {
+        if let Some(call) = pending.remove(&response.request_id) {
+            let _ = call.sender.send(Ok(response));
+        }
+    } else if let Some(call) = pending.get(&response.request_id) {
+        let _ = call.sender.send(Ok(response));
+    }
 }
 
 #[cfg(test)]
@@ -253,6 +856,7 @@ mod tests {
     use super::*;
     use std::fs;
     use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
     use std::os::unix::net::UnixListener;
     use std::thread;
 
@@ -281,7 +885,7 @@ mod tests {
             let response = json!({
                 "ok": true,
                 "op": "ping",
-                "request_id": "",
+                "request_id": req["request_id"],
                 "run_id": "",
                 "tool_call_id": "",
                 "ts_ms": 1,
@@ -304,6 +908,42 @@ mod tests {
         let _ = fs::remove_file(path);
     }
 
+    #[test]
+    fn connect_tcp_roundtrip_ping() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("local_addr");
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("read line");
+            let req: Value = serde_json::from_str(line.trim()).expect("parse req");
+            assert_eq!(req["op"], "ping");
+            let response = json!({
+                "ok": true,
+                "op": "ping",
+                "request_id": req["request_id"],
+                "run_id": "",
+                "tool_call_id": "",
+                "ts_ms": 1,
+                "dur_us": 2,
+                "result": { "pong": true }
+            });
+            let mut inner = reader.into_inner();
+            inner
+                .write_all(format!("{}\n", response).as_bytes())
+                .expect("write");
+        });
+
+        let client = SeqClient::connect_tcp(addr).expect("connect_tcp");
+        assert!(matches!(client.endpoint(), Endpoint::Tcp(a) if *a == addr));
+        let response = client.ping().expect("call");
+        assert!(response.ok);
+        assert_eq!(response.result.unwrap()["pong"], true);
+
+        server.join().expect("join");
+    }
+
     #[test]
     fn call_ok_surfaces_remote_error() {
         let path = test_socket_path("err");
@@ -313,10 +953,11 @@ mod tests {
             let mut reader = BufReader::new(stream);
             let mut line = String::new();
             reader.read_line(&mut line).expect("read line");
+            let req: Value = serde_json::from_str(line.trim()).expect("parse req");
             let response = json!({
                 "ok": false,
                 "op": "open_app",
-                "request_id": "r1",
+                "request_id": req["request_id"],
                 "run_id": "",
                 "tool_call_id": "",
                 "ts_ms": 10,
@@ -341,5 +982,229 @@ mod tests {
         server.join().expect("join");
         let _ = fs::remove_file(path);
     }
+
+    #[test]
+    fn subscribe_receives_pushed_events() {
+        let path = test_socket_path("events");
+        let listener = UnixListener::bind(&path).expect("bind");
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("read line");
+            let req: Value = serde_json::from_str(line.trim()).expect("parse req");
+            assert_eq!(req["op"], "subscribe");
+
+            let mut inner = reader.into_inner();
+            let ack = json!({
+                "ok": true,
+                "op": "subscribe",
+                "request_id": req["request_id"],
+                "run_id": "",
+                "tool_call_id": "",
+                "ts_ms": 1,
+                "dur_us": 1,
+                "result": {}
+            });
+            inner
+                .write_all(format!("{}\n", ack).as_bytes())
+                .expect("write ack");
+
+            let event = json!({
+                "type": "event",
+                "event": "app_focus_changed",
+                "ts_ms": 42,
+                "body": { "name": "Finder" }
+            });
+            inner
+                .write_all(format!("{}\n", event).as_bytes())
+                .expect("write event");
+        });
+
+        let client = SeqClient::connect(&path).expect("connect");
+        let events = client.subscribe().expect("subscribe");
+        let event = events
+            .recv_timeout(Duration::from_secs(2))
+            .expect("event");
+        assert_eq!(event.event, "app_focus_changed");
+        assert_eq!(event.body["name"], "Finder");
+
+        server.join().expect("join");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn call_stream_yields_frames_until_terminal() {
+        let path = test_socket_path("stream");
+        let listener = UnixListener::bind(&path).expect("bind");
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream);
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("read line");
+            let req: Value = serde_json::from_str(line.trim()).expect("parse req");
+            assert_eq!(req["op"], "run_macro");
+
+            let mut inner = reader.into_inner();
+            for step in 0..3 {
+                let response = json!({
+                    "ok": true,
+                    "op": "run_macro",
+                    "request_id": req["request_id"],
+                    "run_id": "",
+                    "tool_call_id": "",
+                    "ts_ms": step,
+                    "dur_us": 1,
+                    "result": { "step": step },
+                    "stream": true
+                });
+                inner
+                    .write_all(format!("{}\n", response).as_bytes())
+                    .expect("write progress frame");
+            }
+            let done = json!({
+                "ok": true,
+                "op": "run_macro",
+                "request_id": req["request_id"],
+                "run_id": "",
+                "tool_call_id": "",
+                "ts_ms": 3,
+                "dur_us": 1,
+                "result": { "step": 3, "finished": true },
+                "stream": false
+            });
+            inner
+                .write_all(format!("{}\n", done).as_bytes())
+                .expect("write final frame");
+        });
+
+        let client = SeqClient::connect(&path).expect("connect");
+        let frames: Vec<RpcResponse> = client
+            .call_stream(RpcRequest::new("run_macro"))
+            .expect("call_stream")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("all frames ok");
+
+        assert_eq!(frames.len(), 4);
+        assert!(frames[..3].iter().all(|f| f.stream));
+        assert!(!frames[3].stream);
+        assert_eq!(frames[3].result.as_ref().unwrap()["finished"], true);
+
+        server.join().expect("join");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn read_loop_handles_multiple_frames_in_one_read() {
+        let path = test_socket_path("multiframe");
+        let listener = UnixListener::bind(&path).expect("bind");
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+            let mut first_line = String::new();
+            let mut second_line = String::new();
+            reader.read_line(&mut first_line).expect("read first request");
+            reader.read_line(&mut second_line).expect("read second request");
+            let first_req: Value = serde_json::from_str(first_line.trim()).expect("parse first");
+            let second_req: Value = serde_json::from_str(second_line.trim()).expect("parse second");
+
+            // Write both responses in a single syscall so the client's
+            // reader sees two frames arrive in one `read()`.
+            let mut batched = Vec::new();
+            for req in [&first_req, &second_req] {
+                let response = json!({
+                    "ok": true,
+                    "op": "ping",
+                    "request_id": req["request_id"],
+                    "run_id": "",
+                    "tool_call_id": "",
+                    "ts_ms": 1,
+                    "dur_us": 2,
+                    "result": { "request_id": req["request_id"] }
+                });
+                batched.extend_from_slice(format!("{}\n", response).as_bytes());
+            }
+            let mut inner = reader.into_inner();
+            inner.write_all(&batched).expect("write batched responses");
+        });
+
+        let client = SeqClient::connect(&path).expect("connect");
+        let (first, second) = thread::scope(|scope| {
+            let first_handle =
+                scope.spawn(|| client.call(RpcRequest::new("ping").with_request_id("first")));
+            let second_handle =
+                scope.spawn(|| client.call(RpcRequest::new("ping").with_request_id("second")));
+            (
+                first_handle.join().expect("first thread"),
+                second_handle.join().expect("second thread"),
+            )
+        });
+
+        let first = first.expect("first call");
+        let second = second.expect("second call");
+        assert_eq!(first.result.unwrap()["request_id"], "first");
+        assert_eq!(second.result.unwrap()["request_id"], "second");
+
+        server.join().expect("join");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn connect_resilient_replays_idempotent_call_after_reconnect() {
+        let path = test_socket_path("resilient");
+        let listener = UnixListener::bind(&path).expect("bind");
+
+        let server = thread::spawn(move || {
+            // First connection: reads the request (so the client's write
+            // genuinely succeeded) then drops without responding, like a
+            // daemon restarting mid-call.
+            let (first, _) = listener.accept().expect("accept first");
+            {
+                let mut reader = BufReader::new(first.try_clone().expect("clone first"));
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("read first request");
+            }
+            drop(first);
+
+            // Second connection: the supervisor's reconnect. Serve the
+            // replayed request for real.
+            let (second, _) = listener.accept().expect("accept second");
+            let mut reader = BufReader::new(second);
+            let mut line = String::new();
+            reader.read_line(&mut line).expect("read replayed request");
+            let req: Value = serde_json::from_str(line.trim()).expect("parse req");
+            assert_eq!(req["op"], "ping");
+            let response = json!({
+                "ok": true,
+                "op": "ping",
+                "request_id": req["request_id"],
+                "run_id": "",
+                "tool_call_id": "",
+                "ts_ms": 1,
+                "dur_us": 2,
+                "result": { "pong": true }
+            });
+            let mut inner = reader.into_inner();
+            inner
+                .write_all(format!("{}\n", response).as_bytes())
+                .expect("write");
+        });
+
+        let config = ReconnectConfig {
+            initial_backoff: Duration::from_millis(5),
+            max_backoff: Duration::from_millis(20),
+            max_attempts: Some(20),
+        };
+        let client = SeqClient::connect_resilient(&path, config).expect("connect_resilient");
+        let response = client
+            .call(RpcRequest::new("ping").idempotent())
+            .expect("call should succeed once replayed on the reconnected socket");
+        assert!(response.ok);
+        assert_eq!(response.result.unwrap()["pong"], true);
+
+        server.join().expect("join");
+        let _ = fs::remove_file(path);
+    }
 }
 
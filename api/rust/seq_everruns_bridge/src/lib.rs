@@ -1,7 +1,11 @@
 use seq_client::{RpcRequest, SeqClient};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 pub mod maple;
@@ -21,6 +25,30 @@ pub struct ToolResult {
     pub result: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<ToolErrorDetail>,
+}
+
+/// Structured diagnostics carried alongside `ToolResult.error` so a failure
+/// is debuggable from the trace alone instead of collapsing into one opaque
+/// string.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ToolErrorDetail {
+    pub seq_op: String,
+    pub request_id: String,
+    pub run_id: String,
+    pub tool_call_id: String,
+    /// `call.arguments` with sensitive-looking keys masked; see
+    /// [`redact_args_snapshot`].
+    pub args_snapshot: Value,
+    /// The raw `error` payload seqd returned, if the failure came from a
+    /// daemon response rather than a client-side/transport error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daemon_error: Option<String>,
+    /// Same payload, only populated when it spans multiple lines (i.e. looks
+    /// like a stack trace rather than a short message).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daemon_trace: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,13 +67,58 @@ pub fn parse_tool_call_requested(data: &Value) -> Result<Vec<ToolCall>, serde_js
     Ok(parsed.tool_calls)
 }
 
+const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_RETRY_BASE_DELAY_MS: u64 = 200;
+const DEFAULT_RETRY_MULTIPLIER: f64 = 2.0;
+const DEFAULT_RETRY_MAX_DELAY_MS: u64 = 5_000;
+
+/// Upper bound on threads spawned to run one dependency layer concurrently.
+/// Layers are usually small, but an attacker- or model-generated batch could
+/// ask for thousands of independent calls in one layer; capping the pool
+/// keeps that bounded instead of spawning one OS thread per call.
+const DEFAULT_TOOL_CALL_WORKERS: usize = 8;
+
+/// Substrings (checked case-insensitively) that mark a tool-call failure as
+/// transient and therefore worth retrying. Anything else (unsupported tool,
+/// invalid args, a seq-side rejection) is treated as permanent.
+const TRANSIENT_ERROR_MARKERS: [&str; 6] = [
+    "timed out",
+    "timeout",
+    "connection reset",
+    "broken pipe",
+    "daemon busy",
+    "would block",
+];
+
+/// Backoff policy for [`execute_tool_call_with_retry`]: delay doubles (by
+/// default) each attempt as `base_delay * multiplier^(attempt-1)`, capped at
+/// `max_delay`, with jitter layered on top.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToolCallRetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub multiplier: f64,
+    pub max_delay: Duration,
+}
+
+impl Default for ToolCallRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(DEFAULT_RETRY_BASE_DELAY_MS),
+            multiplier: DEFAULT_RETRY_MULTIPLIER,
+            max_delay: Duration::from_millis(DEFAULT_RETRY_MAX_DELAY_MS),
+        }
+    }
+}
+
 pub fn execute_tool_call(
     client: &SeqClient,
     session_id: &str,
     event_id: &str,
     call: &ToolCall,
 ) -> ToolResult {
-    execute_tool_call_with_maple(client, session_id, event_id, call, None)
+    execute_tool_call_with_maple(client, session_id, event_id, call, None, None)
 }
 
 pub fn execute_tool_call_with_maple(
@@ -54,54 +127,255 @@ pub fn execute_tool_call_with_maple(
     event_id: &str,
     call: &ToolCall,
     maple_exporter: Option<&maple::MapleTraceExporter>,
+    parent_span_id: Option<&str>,
+) -> ToolResult {
+    execute_tool_call_with_retry(
+        client,
+        session_id,
+        event_id,
+        call,
+        maple_exporter,
+        parent_span_id,
+        ToolCallRetryPolicy::default(),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_tool_call_with_retry(
+    client: &SeqClient,
+    session_id: &str,
+    event_id: &str,
+    call: &ToolCall,
+    maple_exporter: Option<&maple::MapleTraceExporter>,
+    parent_span_id: Option<&str>,
+    retry_policy: ToolCallRetryPolicy,
 ) -> ToolResult {
     let started = Instant::now();
     let start_unix_nano = unix_time_nanos_now();
     let seq_op = map_tool_name_to_seq_op(&call.name).unwrap_or("unknown");
 
-    let result = match build_request(session_id, event_id, call) {
-        Ok(req) => match client.call(req) {
-            Ok(resp) => {
-                if resp.ok {
-                    ToolResult {
-                        tool_call_id: call.id.clone(),
-                        result: Some(resp.result.unwrap_or_else(|| json!({}))),
-                        error: None,
-                    }
-                } else {
-                    let op = map_tool_name_to_seq_op(&call.name).unwrap_or("unknown");
-                    ToolResult {
-                        tool_call_id: call.id.clone(),
-                        result: None,
-                        error: Some(
-                            resp.error
-                                .unwrap_or_else(|| format!("seq {op} failed with unknown error")),
-                        ),
-                    }
+    let req = match build_request(session_id, event_id, call) {
+        Ok(req) => req,
+        Err(err) => {
+            let result = ToolResult {
+                tool_call_id: call.id.clone(),
+                result: None,
+                error: Some(err.to_string()),
+                error_detail: Some(build_error_detail(session_id, event_id, call, seq_op, None)),
+            };
+            emit_tool_call_span(
+                maple_exporter,
+                session_id,
+                event_id,
+                call,
+                seq_op,
+                &result,
+                start_unix_nano,
+                started.elapsed(),
+                1,
+                parent_span_id,
+                None,
+            );
+            return result;
+        }
+    };
+
+    if seq_op == "wait_for" {
+        let (result, attempts, wait_for_event) = execute_wait_for(client, session_id, event_id, call);
+        emit_tool_call_span(
+            maple_exporter,
+            session_id,
+            event_id,
+            call,
+            seq_op,
+            &result,
+            start_unix_nano,
+            started.elapsed(),
+            attempts as u64,
+            parent_span_id,
+            wait_for_event,
+        );
+        return result;
+    }
+
+    let mut attempt = 1;
+    let result = loop {
+        let attempt_result = dispatch_tool_call(client, session_id, event_id, call, seq_op, &req);
+        let retryable = attempt_result
+            .error
+            .as_deref()
+            .is_some_and(is_transient_error);
+
+        if !retryable || attempt >= retry_policy.max_attempts {
+            break attempt_result;
+        }
+
+        thread::sleep(retry_backoff_delay(&retry_policy, attempt));
+        attempt += 1;
+    };
+
+    emit_tool_call_span(
+        maple_exporter,
+        session_id,
+        event_id,
+        call,
+        seq_op,
+        &result,
+        start_unix_nano,
+        started.elapsed(),
+        attempt as u64,
+        parent_span_id,
+        None,
+    );
+
+    result
+}
+
+fn dispatch_tool_call(
+    client: &SeqClient,
+    session_id: &str,
+    event_id: &str,
+    call: &ToolCall,
+    seq_op: &str,
+    req: &RpcRequest,
+) -> ToolResult {
+    match client.call(req.clone()) {
+        Ok(resp) => {
+            if resp.ok {
+                ToolResult {
+                    tool_call_id: call.id.clone(),
+                    result: Some(resp.result.unwrap_or_else(|| json!({}))),
+                    error: None,
+                    error_detail: None,
                 }
-            }
-            Err(err) => {
-                let op = map_tool_name_to_seq_op(&call.name).unwrap_or("unknown");
+            } else {
+                let daemon_error = resp
+                    .error
+                    .unwrap_or_else(|| format!("seq {seq_op} failed with unknown error"));
+                let detail = build_error_detail(
+                    session_id,
+                    event_id,
+                    call,
+                    seq_op,
+                    Some(&daemon_error),
+                );
                 ToolResult {
                     tool_call_id: call.id.clone(),
                     result: None,
-                    error: Some(format!("seq {op} call failed: {err}")),
+                    error: Some(daemon_error),
+                    error_detail: Some(detail),
                 }
             }
-        },
+        }
         Err(err) => ToolResult {
             tool_call_id: call.id.clone(),
             result: None,
-            error: Some(err.to_string()),
+            error: Some(format!("seq {seq_op} call failed: {err}")),
+            error_detail: Some(build_error_detail(session_id, event_id, call, seq_op, None)),
         },
-    };
+    }
+}
+
+/// Builds the structured diagnostics attached to a failed [`ToolResult`].
+/// `daemon_error` is `Some` only when the failure is a payload seqd actually
+/// returned (as opposed to a client-side/transport error or an unsupported
+/// tool that never reached the daemon).
+fn build_error_detail(
+    session_id: &str,
+    event_id: &str,
+    call: &ToolCall,
+    seq_op: &str,
+    daemon_error: Option<&str>,
+) -> ToolErrorDetail {
+    ToolErrorDetail {
+        seq_op: seq_op.to_string(),
+        request_id: format!("everruns:{event_id}:{}", call.id),
+        run_id: session_id.to_string(),
+        tool_call_id: call.id.clone(),
+        args_snapshot: redact_args_snapshot(&call.arguments),
+        daemon_error: daemon_error.map(|s| s.to_string()),
+        daemon_trace: daemon_error
+            .filter(|s| s.contains('\n'))
+            .map(|s| s.to_string()),
+    }
+}
+
+/// Argument keys (matched case-insensitively as a substring) whose values
+/// get masked before a [`ToolErrorDetail`] snapshot leaves the process.
+const SENSITIVE_ARG_KEY_MARKERS: [&str; 4] = ["password", "secret", "token", "credential"];
+
+fn redact_args_snapshot(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut redacted = serde_json::Map::with_capacity(map.len());
+            for (key, inner) in map {
+                let lower = key.to_ascii_lowercase();
+                if SENSITIVE_ARG_KEY_MARKERS
+                    .iter()
+                    .any(|marker| lower.contains(marker))
+                {
+                    redacted.insert(key.clone(), json!("***redacted***"));
+                } else {
+                    redacted.insert(key.clone(), redact_args_snapshot(inner));
+                }
+            }
+            Value::Object(redacted)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(redact_args_snapshot).collect()),
+        other => other.clone(),
+    }
+}
 
+fn is_transient_error(message: &str) -> bool {
+    let lower = message.to_ascii_lowercase();
+    TRANSIENT_ERROR_MARKERS
+        .iter()
+        .any(|marker| lower.contains(marker))
+}
+
+fn retry_backoff_delay(policy: &ToolCallRetryPolicy, attempt: u32) -> Duration {
+    let exp_ms = policy.base_delay.as_millis() as f64 * policy.multiplier.powi(attempt as i32 - 1);
+    let capped_ms = exp_ms.min(policy.max_delay.as_millis() as f64).max(0.0);
+    let jitter = retry_jitter_fraction(attempt as u64 ^ capped_ms as u64);
+    let jittered_ms = (capped_ms * (1.0 + jitter)).max(0.0) as u64;
+    Duration::from_millis(jittered_ms)
+}
+
+/// Jitter in `[-0.2, 0.2]` derived from the current time and `seed`, so
+/// concurrent retries of the same tool call don't line up in lockstep.
+fn retry_jitter_fraction(seed: u64) -> f64 {
+    let now_nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in (seed ^ now_nanos).to_le_bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let normalized = (hash % 1000) as f64 / 1000.0;
+    (normalized - 0.5) * 0.4
+}
+
+#[allow(clippy::too_many_arguments)]
+fn emit_tool_call_span(
+    maple_exporter: Option<&maple::MapleTraceExporter>,
+    session_id: &str,
+    event_id: &str,
+    call: &ToolCall,
+    seq_op: &str,
+    result: &ToolResult,
+    start_unix_nano: u64,
+    elapsed: Duration,
+    attempts: u64,
+    parent_span_id: Option<&str>,
+    extra_event: Option<maple::MapleSpanEvent>,
+) {
     if let Some(exporter) = maple_exporter {
-        let elapsed = started.elapsed();
         let duration_ms = elapsed.as_millis() as u64;
         let end_unix_nano = start_unix_nano.saturating_add(elapsed.as_nanos() as u64);
         let ok = result.error.is_none();
-        let span = maple::MapleSpan::for_tool_call(
+        let mut span = maple::MapleSpan::for_tool_call(
             session_id,
             event_id,
             &call.id,
@@ -112,11 +386,698 @@ pub fn execute_tool_call_with_maple(
             start_unix_nano,
             end_unix_nano,
             duration_ms,
+            parent_span_id,
+            attempts,
         );
+        if let Some(detail) = &result.error_detail {
+            span.events.push(error_detail_span_event(detail, end_unix_nano));
+        }
+        if let Some(event) = extra_event {
+            span.events.push(event);
+        }
         exporter.emit_span(span);
     }
+}
 
-    result
+fn error_detail_span_event(detail: &ToolErrorDetail, time_unix_nano: u64) -> maple::MapleSpanEvent {
+    maple::MapleSpanEvent {
+        time_unix_nano,
+        name: "tool_call.error".to_string(),
+        attributes: vec![
+            ("seq_op".to_string(), detail.seq_op.clone().into()),
+            ("request_id".to_string(), detail.request_id.clone().into()),
+            ("run_id".to_string(), detail.run_id.clone().into()),
+            ("tool_call_id".to_string(), detail.tool_call_id.clone().into()),
+            (
+                "args_snapshot".to_string(),
+                detail.args_snapshot.to_string().into(),
+            ),
+            (
+                "daemon_error".to_string(),
+                detail.daemon_error.clone().unwrap_or_default().into(),
+            ),
+            (
+                "daemon_trace".to_string(),
+                detail.daemon_trace.clone().unwrap_or_default().into(),
+            ),
+        ],
+    }
+}
+
+const DEFAULT_WAIT_FOR_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_WAIT_FOR_INTERVAL_MS: u64 = 250;
+
+fn default_wait_for_timeout_ms() -> u64 {
+    DEFAULT_WAIT_FOR_TIMEOUT_MS
+}
+
+fn default_wait_for_interval_ms() -> u64 {
+    DEFAULT_WAIT_FOR_INTERVAL_MS
+}
+
+/// Which seq probe op [`execute_wait_for`] polls.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum WaitForProbe {
+    AppState,
+    Perf,
+}
+
+impl WaitForProbe {
+    fn seq_op(self) -> &'static str {
+        match self {
+            WaitForProbe::AppState => "app_state",
+            WaitForProbe::Perf => "perf",
+        }
+    }
+}
+
+/// Arguments for the `wait_for` op: poll `probe` on `interval_ms` and compare
+/// the value at `path` (a dotted or JSON-pointer path) against exactly one of
+/// `equals`, `matches`, or `exists`, until it holds or `timeout_ms` elapses.
+#[derive(Debug, Deserialize)]
+struct WaitForArgs {
+    probe: WaitForProbe,
+    #[serde(default)]
+    path: String,
+    #[serde(default)]
+    equals: Option<Value>,
+    #[serde(default)]
+    matches: Option<String>,
+    #[serde(default)]
+    exists: Option<bool>,
+    #[serde(default = "default_wait_for_timeout_ms")]
+    timeout_ms: u64,
+    #[serde(default = "default_wait_for_interval_ms")]
+    interval_ms: u64,
+}
+
+/// Checks `observed` (the value already extracted at `args.path`) against
+/// whichever predicate field `args` sets. Exactly one of `equals` / `matches`
+/// / `exists` is expected; if none are set that's a configuration error, not
+/// a non-match.
+fn wait_for_predicate_holds(args: &WaitForArgs, observed: &Value) -> Result<bool, String> {
+    if let Some(expected) = &args.equals {
+        return Ok(observed == expected);
+    }
+    if let Some(pattern) = &args.matches {
+        let text = match observed {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        return Ok(text.contains(pattern.as_str()));
+    }
+    if let Some(expect_present) = args.exists {
+        return Ok(!observed.is_null() == expect_present);
+    }
+    Err("wait_for requires one of \"equals\", \"matches\", or \"exists\"".to_string())
+}
+
+fn wait_for_pointer(path: &str) -> String {
+    if path.is_empty() || path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path.replace('.', "/"))
+    }
+}
+
+/// Polls `args.probe` on `args.interval_ms` until the value at `args.path`
+/// satisfies the predicate or `args.timeout_ms` elapses. Returns the final
+/// [`ToolResult`], how many probe calls it took, and a span event describing
+/// the poll for [`emit_tool_call_span`].
+fn execute_wait_for(
+    client: &SeqClient,
+    session_id: &str,
+    event_id: &str,
+    call: &ToolCall,
+) -> (ToolResult, u32, Option<maple::MapleSpanEvent>) {
+    let args: WaitForArgs = match serde_json::from_value(call.arguments.clone()) {
+        Ok(args) => args,
+        Err(err) => {
+            return (
+                ToolResult {
+                    tool_call_id: call.id.clone(),
+                    result: None,
+                    error: Some(format!("invalid wait_for arguments: {err}")),
+                    error_detail: None,
+                },
+                0,
+                None,
+            );
+        }
+    };
+
+    let probe_req = RpcRequest::new(args.probe.seq_op())
+        .with_request_id(format!("everruns:{event_id}:{}", call.id))
+        .with_run_id(session_id)
+        .with_tool_call_id(&call.id);
+    let pointer = wait_for_pointer(&args.path);
+    let poll_started = Instant::now();
+    let deadline = poll_started + Duration::from_millis(args.timeout_ms);
+
+    let mut attempts: u32 = 0;
+    let mut last_observed = Value::Null;
+    let result = loop {
+        attempts += 1;
+
+        let probe_result = match client.call(probe_req.clone()) {
+            Ok(resp) if resp.ok => Ok(resp.result.unwrap_or(Value::Null)),
+            Ok(resp) => Err(resp
+                .error
+                .unwrap_or_else(|| format!("seq {} failed with unknown error", args.probe.seq_op()))),
+            Err(err) => Err(format!("seq {} call failed: {err}", args.probe.seq_op())),
+        };
+
+        let observed = match probe_result {
+            Ok(observed) => observed,
+            Err(message) => break wait_for_error_result(call, message),
+        };
+
+        let at_path = if pointer.is_empty() {
+            observed.clone()
+        } else {
+            observed.pointer(&pointer).cloned().unwrap_or(Value::Null)
+        };
+        last_observed = at_path.clone();
+
+        match wait_for_predicate_holds(&args, &at_path) {
+            Ok(true) => {
+                break ToolResult {
+                    tool_call_id: call.id.clone(),
+                    result: Some(json!({ "matched": true, "value": at_path, "attempts": attempts })),
+                    error: None,
+                    error_detail: None,
+                };
+            }
+            Ok(false) => {}
+            Err(message) => break wait_for_error_result(call, message),
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            break wait_for_error_result(
+                call,
+                format!(
+                    "wait_for timed out after {}ms (last observed: {last_observed})",
+                    args.timeout_ms
+                ),
+            );
+        }
+
+        let remaining = deadline.saturating_duration_since(now);
+        thread::sleep(Duration::from_millis(args.interval_ms).min(remaining));
+    };
+
+    let event = Some(wait_for_span_event(
+        &args,
+        attempts,
+        poll_started.elapsed().as_millis() as u64,
+        &last_observed,
+    ));
+    (result, attempts, event)
+}
+
+fn wait_for_error_result(call: &ToolCall, message: String) -> ToolResult {
+    ToolResult {
+        tool_call_id: call.id.clone(),
+        result: None,
+        error: Some(message),
+        error_detail: None,
+    }
+}
+
+fn wait_for_span_event(
+    args: &WaitForArgs,
+    attempts: u32,
+    elapsed_ms: u64,
+    last_observed: &Value,
+) -> maple::MapleSpanEvent {
+    maple::MapleSpanEvent {
+        time_unix_nano: unix_time_nanos_now(),
+        name: "tool_call.wait_for".to_string(),
+        attributes: vec![
+            ("wait_for.probe".to_string(), args.probe.seq_op().to_string().into()),
+            ("wait_for.path".to_string(), args.path.clone().into()),
+            ("wait_for.interval_ms".to_string(), args.interval_ms.into()),
+            ("wait_for.timeout_ms".to_string(), args.timeout_ms.into()),
+            ("wait_for.poll_attempts".to_string(), (attempts as u64).into()),
+            ("wait_for.poll_duration_ms".to_string(), elapsed_ms.into()),
+            ("wait_for.last_value".to_string(), last_observed.to_string().into()),
+        ],
+    }
+}
+
+/// How [`execute_tool_calls`] should react when one call in the batch fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCallMode {
+    /// Abort any call still waiting on a failed dependency and report it as
+    /// skipped, but let sibling calls that don't depend on the failure run.
+    StopOnError,
+    /// Run every call regardless of earlier failures; calls that reference a
+    /// failed result simply fail themselves when the placeholder can't
+    /// resolve.
+    ContinueOnError,
+}
+
+/// Runs a batch of [`ToolCall`]s, resolving `{{tool_call_id.result.path}}`
+/// placeholders in later calls' `arguments` against the results of earlier
+/// ones.
+///
+/// Calls are grouped into dependency layers via a topological sort over the
+/// placeholders they reference, and every call within a layer is dispatched
+/// onto a small worker pool (see [`DEFAULT_TOOL_CALL_WORKERS`]) since layers
+/// have no remaining dependencies among themselves. In [`ToolCallMode::StopOnError`],
+/// a call is skipped only if it actually (transitively) depends on a call
+/// that failed or was itself skipped — a sibling whose own dependencies all
+/// succeeded still runs. See [`execute_tool_calls_with_maple`] to also emit
+/// a parent span covering the whole batch.
+pub fn execute_tool_calls(
+    client: &SeqClient,
+    session_id: &str,
+    event_id: &str,
+    calls: &[ToolCall],
+    mode: ToolCallMode,
+) -> Vec<ToolResult> {
+    execute_tool_calls_with_maple(client, session_id, event_id, calls, mode, None)
+}
+
+pub fn execute_tool_calls_with_maple(
+    client: &SeqClient,
+    session_id: &str,
+    event_id: &str,
+    calls: &[ToolCall],
+    mode: ToolCallMode,
+    maple_exporter: Option<&maple::MapleTraceExporter>,
+) -> Vec<ToolResult> {
+    if calls.is_empty() {
+        return Vec::new();
+    }
+
+    let trace_ctx = maple::MapleTraceContext::new(session_id, event_id);
+    let batch_guard = maple_exporter.map(|_| {
+        trace_ctx.start_child(
+            &format!("{session_id}:{event_id}:batch:{}", calls.len()),
+            "everruns.tool_call_batch",
+            1,
+        )
+    });
+    let parent_span_id = batch_guard.as_ref().map(|guard| guard.span_id().to_string());
+    let parent_span_id = parent_span_id.as_deref();
+
+    let layers = match topo_sort_layers(calls) {
+        Ok(layers) => layers,
+        Err(cycle_id) => {
+            return calls
+                .iter()
+                .map(|call| ToolResult {
+                    tool_call_id: call.id.clone(),
+                    result: None,
+                    error: Some(format!(
+                        "tool call dependency graph has a cycle involving \"{cycle_id}\""
+                    )),
+                    error_detail: None,
+                })
+                .collect();
+        }
+    };
+
+    let known_ids: HashSet<&str> = calls.iter().map(|call| call.id.as_str()).collect();
+    let deps_by_id: HashMap<&str, HashSet<String>> = calls
+        .iter()
+        .map(|call| (call.id.as_str(), call_dependencies(call, &known_ids)))
+        .collect();
+
+    let calls_by_id: HashMap<&str, &ToolCall> =
+        calls.iter().map(|call| (call.id.as_str(), call)).collect();
+    let mut results: HashMap<String, ToolResult> = HashMap::new();
+    let mut failed_ids: HashSet<String> = HashSet::new();
+
+    for layer in layers {
+        let mut layer_results = Vec::with_capacity(layer.len());
+        let mut to_run = Vec::with_capacity(layer.len());
+
+        for id in layer {
+            let blocking = blocking_deps(&id, &deps_by_id, &failed_ids, mode);
+            if blocking.is_empty() {
+                to_run.push(id);
+            } else {
+                layer_results.push((id.clone(), skipped_result(&id, &blocking)));
+            }
+        }
+
+        let next_index = AtomicUsize::new(0);
+        let run_results: Mutex<Vec<Option<(String, ToolResult)>>> =
+            Mutex::new((0..to_run.len()).map(|_| None).collect());
+        let worker_count = DEFAULT_TOOL_CALL_WORKERS.min(to_run.len().max(1));
+
+        thread::scope(|scope| {
+            for _ in 0..worker_count {
+                scope.spawn(|| loop {
+                    let idx = next_index.fetch_add(1, Ordering::Relaxed);
+                    if idx >= to_run.len() {
+                        break;
+                    }
+                    let id = &to_run[idx];
+                    let call = calls_by_id[id.as_str()];
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        run_chained_call(
+                            client,
+                            session_id,
+                            event_id,
+                            call,
+                            &results,
+                            parent_span_id,
+                            maple_exporter,
+                        )
+                    }))
+                    .unwrap_or_else(|_| ToolResult {
+                        tool_call_id: id.clone(),
+                        result: None,
+                        error: Some("tool call worker panicked".to_string()),
+                        error_detail: None,
+                    });
+                    run_results
+                        .lock()
+                        .expect("tool call results mutex poisoned")[idx] = Some((id.clone(), result));
+                });
+            }
+        });
+
+        layer_results.extend(
+            run_results
+                .into_inner()
+                .expect("tool call results mutex poisoned")
+                .into_iter()
+                .map(|slot| slot.expect("every index is assigned to exactly one worker")),
+        );
+
+        for (id, result) in layer_results {
+            if mode == ToolCallMode::StopOnError && result.error.is_some() {
+                failed_ids.insert(id.clone());
+            }
+            results.insert(id, result);
+        }
+    }
+
+    let ordered = calls
+        .iter()
+        .map(|call| {
+            results.remove(&call.id).unwrap_or_else(|| ToolResult {
+                tool_call_id: call.id.clone(),
+                result: None,
+                error: Some("skipped: an upstream dependency failed".to_string()),
+                error_detail: None,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if let (Some(mut guard), Some(exporter)) = (batch_guard, maple_exporter) {
+        guard.set_status(ordered.iter().all(|r| r.error.is_none()), None);
+        exporter.emit_span(guard.end());
+    }
+
+    ordered
+}
+
+/// Returns the ids (from `call_id`'s direct dependencies) that already
+/// failed or were themselves skipped, i.e. the ids `call_id` must be
+/// skipped on account of. Empty for [`ToolCallMode::ContinueOnError`], and
+/// empty whenever every dependency of `call_id` succeeded — a call is only
+/// ever blocked by its own (transitive, via `failed_ids` carrying earlier
+/// skips forward) dependencies, never by unrelated failures elsewhere in
+/// the batch.
+fn blocking_deps<'a>(
+    call_id: &str,
+    deps_by_id: &HashMap<&str, HashSet<String>>,
+    failed_ids: &'a HashSet<String>,
+    mode: ToolCallMode,
+) -> Vec<&'a str> {
+    if mode != ToolCallMode::StopOnError {
+        return Vec::new();
+    }
+    deps_by_id[call_id]
+        .iter()
+        .filter_map(|dep| failed_ids.get(dep).map(String::as_str))
+        .collect()
+}
+
+/// Builds the [`ToolResult`] for a call skipped in [`ToolCallMode::StopOnError`]
+/// because it transitively depends on `blocking`, which already failed or
+/// was itself skipped.
+fn skipped_result(call_id: &str, blocking: &[&str]) -> ToolResult {
+    let deps = blocking.join("\", \"");
+    let message = if blocking.len() == 1 {
+        format!("skipped: upstream dependency \"{deps}\" failed")
+    } else {
+        format!("skipped: upstream dependencies \"{deps}\" failed")
+    };
+    ToolResult {
+        tool_call_id: call_id.to_string(),
+        result: None,
+        error: Some(message),
+        error_detail: None,
+    }
+}
+
+fn run_chained_call(
+    client: &SeqClient,
+    session_id: &str,
+    event_id: &str,
+    call: &ToolCall,
+    prior_results: &HashMap<String, ToolResult>,
+    parent_span_id: Option<&str>,
+    maple_exporter: Option<&maple::MapleTraceExporter>,
+) -> ToolResult {
+    let arguments = match resolve_placeholders(&call.arguments, prior_results) {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            return ToolResult {
+                tool_call_id: call.id.clone(),
+                result: None,
+                error: Some(format!("failed to resolve templated arguments: {message}")),
+                error_detail: None,
+            };
+        }
+    };
+
+    let resolved_call = ToolCall {
+        id: call.id.clone(),
+        name: call.name.clone(),
+        arguments,
+    };
+
+    execute_tool_call_with_maple(
+        client,
+        session_id,
+        event_id,
+        &resolved_call,
+        maple_exporter,
+        parent_span_id,
+    )
+}
+
+/// Groups `calls` into waves that can each run concurrently: a call lands in
+/// the first wave after every call it references via a `{{tc_id...}}`
+/// placeholder. Returns `Err(tool_call_id)` naming one call caught in a
+/// dependency cycle.
+fn topo_sort_layers(calls: &[ToolCall]) -> Result<Vec<Vec<String>>, String> {
+    let known_ids: HashSet<&str> = calls.iter().map(|call| call.id.as_str()).collect();
+    let deps: HashMap<String, HashSet<String>> = calls
+        .iter()
+        .map(|call| (call.id.clone(), call_dependencies(call, &known_ids)))
+        .collect();
+
+    let mut remaining: HashSet<String> = calls.iter().map(|call| call.id.clone()).collect();
+    let mut layers = Vec::new();
+
+    while !remaining.is_empty() {
+        let ready: Vec<String> = remaining
+            .iter()
+            .filter(|id| {
+                deps[id.as_str()]
+                    .iter()
+                    .all(|dep| !remaining.contains(dep))
+            })
+            .cloned()
+            .collect();
+
+        if ready.is_empty() {
+            let cycle_id = remaining.iter().next().cloned().unwrap_or_default();
+            return Err(cycle_id);
+        }
+
+        for id in &ready {
+            remaining.remove(id);
+        }
+        layers.push(ready);
+    }
+
+    Ok(layers)
+}
+
+fn call_dependencies(call: &ToolCall, known_ids: &HashSet<&str>) -> HashSet<String> {
+    let mut refs = Vec::new();
+    collect_placeholder_refs(&call.arguments, &mut refs);
+    refs.into_iter()
+        .map(|reference| reference.tool_call_id)
+        .filter(|id| id != &call.id && known_ids.contains(id.as_str()))
+        .collect()
+}
+
+/// A single `{{tool_call_id.path}}` reference found inside a call's
+/// `arguments`.
+struct PlaceholderRef {
+    tool_call_id: String,
+    path: String,
+}
+
+fn collect_placeholder_refs(value: &Value, out: &mut Vec<PlaceholderRef>) {
+    match value {
+        Value::String(s) => out.extend(find_placeholders(s)),
+        Value::Array(items) => items.iter().for_each(|v| collect_placeholder_refs(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_placeholder_refs(v, out)),
+        _ => {}
+    }
+}
+
+fn find_placeholders(s: &str) -> Vec<PlaceholderRef> {
+    let mut out = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        if let Some(reference) = parse_placeholder(after[..end].trim()) {
+            out.push(reference);
+        }
+        rest = &after[end + 2..];
+    }
+    out
+}
+
+fn parse_placeholder(inner: &str) -> Option<PlaceholderRef> {
+    let (tool_call_id, path) = inner.split_once('.')?;
+    Some(PlaceholderRef {
+        tool_call_id: tool_call_id.to_string(),
+        path: path.to_string(),
+    })
+}
+
+/// Recursively resolves `{{tool_call_id.result.json.pointer}}` (or
+/// `{{tool_call_id.error}}`) placeholders inside `value` against
+/// `prior_results`. A string that is *entirely* one placeholder is replaced
+/// with the referenced JSON value verbatim (so numbers and objects survive);
+/// a placeholder embedded in a larger string is substituted as text.
+fn resolve_placeholders(
+    value: &Value,
+    prior_results: &HashMap<String, ToolResult>,
+) -> Result<Value, String> {
+    match value {
+        Value::String(s) => resolve_string(s, prior_results),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| resolve_placeholders(item, prior_results))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| resolve_placeholders(v, prior_results).map(|v| (k.clone(), v)))
+            .collect::<Result<serde_json::Map<_, _>, _>>()
+            .map(Value::Object),
+        other => Ok(other.clone()),
+    }
+}
+
+fn resolve_string(s: &str, prior_results: &HashMap<String, ToolResult>) -> Result<Value, String> {
+    let trimmed = s.trim();
+    if trimmed.starts_with("{{") && trimmed.ends_with("}}") && !trimmed[2..trimmed.len() - 2].contains("{{")
+    {
+        let reference = parse_placeholder(trimmed[2..trimmed.len() - 2].trim())
+            .ok_or_else(|| format!("malformed placeholder: {trimmed}"))?;
+        return resolve_placeholder(&reference, prior_results);
+    }
+
+    let mut out = String::new();
+    let mut rest = s;
+    loop {
+        match rest.find("{{") {
+            None => {
+                out.push_str(rest);
+                break;
+            }
+            Some(start) => {
+                out.push_str(&rest[..start]);
+                let after = &rest[start + 2..];
+                match after.find("}}") {
+                    None => {
+                        out.push_str(&rest[start..]);
+                        break;
+                    }
+                    Some(end) => {
+                        let reference = parse_placeholder(after[..end].trim())
+                            .ok_or_else(|| format!("malformed placeholder in: {s}"))?;
+                        let resolved = resolve_placeholder(&reference, prior_results)?;
+                        out.push_str(&display_value(&resolved));
+                        rest = &after[end + 2..];
+                    }
+                }
+            }
+        }
+    }
+    Ok(Value::String(out))
+}
+
+fn resolve_placeholder(
+    reference: &PlaceholderRef,
+    prior_results: &HashMap<String, ToolResult>,
+) -> Result<Value, String> {
+    let tool_result = prior_results.get(&reference.tool_call_id).ok_or_else(|| {
+        format!(
+            "no prior tool result for tool-call id \"{}\"",
+            reference.tool_call_id
+        )
+    })?;
+
+    let mut segments = reference.path.split('.');
+    let root = segments.next().unwrap_or("");
+    let root_value = match root {
+        "result" => tool_result.result.clone().unwrap_or(Value::Null),
+        "error" => tool_result
+            .error
+            .clone()
+            .map(Value::String)
+            .unwrap_or(Value::Null),
+        other => {
+            return Err(format!(
+                "unsupported placeholder root \"{other}\" (expected \"result\" or \"error\")"
+            ))
+        }
+    };
+
+    let pointer = segments.fold(String::new(), |mut acc, segment| {
+        acc.push('/');
+        acc.push_str(segment);
+        acc
+    });
+
+    if pointer.is_empty() {
+        Ok(root_value)
+    } else {
+        root_value.pointer(&pointer).cloned().ok_or_else(|| {
+            format!(
+                "no value at {{{{{}.{}}}}}",
+                reference.tool_call_id, reference.path
+            )
+        })
+    }
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
 }
 
 pub fn build_request(
@@ -163,6 +1124,10 @@ pub fn map_tool_name_to_seq_op(tool_name: &str) -> Option<&'static str> {
         "scroll" => Some("scroll"),
         "drag" => Some("drag"),
         "screenshot" => Some("screenshot"),
+        "type_text" => Some("type_text"),
+        "key_press" => Some("key_press"),
+        "key_combo" => Some("key_combo"),
+        "wait_for" => Some("wait_for"),
         _ => None,
     }
 }
@@ -283,6 +1248,60 @@ pub fn client_side_tool_definitions() -> Vec<Value> {
                 "additionalProperties":false
             }),
         ),
+        client_tool(
+            "seq_type_text",
+            "Type text into the focused field",
+            json!({
+                "type":"object",
+                "properties":{
+                    "text":{"type":"string"},
+                    "wpm":{"type":"number","description":"Typing speed in words per minute (optional)"}
+                },
+                "required":["text"],
+                "additionalProperties":false
+            }),
+        ),
+        client_tool(
+            "seq_key_press",
+            "Press a single named key (e.g. return, escape, tab)",
+            json!({
+                "type":"object",
+                "properties":{"key":{"type":"string","description":"Named key (e.g. return, escape, tab)"}},
+                "required":["key"],
+                "additionalProperties":false
+            }),
+        ),
+        client_tool(
+            "seq_key_combo",
+            "Press a key combo with modifiers (e.g. cmd+t)",
+            json!({
+                "type":"object",
+                "properties":{
+                    "mods":{"type":"array","items":{"type":"string"},"description":"Modifier keys (e.g. cmd, shift, option, ctrl)"},
+                    "key":{"type":"string"}
+                },
+                "required":["mods","key"],
+                "additionalProperties":false
+            }),
+        ),
+        client_tool(
+            "seq_wait_for",
+            "Poll a probe until a JSON-pointer path matches a condition, or time out",
+            json!({
+                "type":"object",
+                "properties":{
+                    "probe":{"type":"string","enum":["app_state","perf"],"description":"Which snapshot op to poll"},
+                    "path":{"type":"string","description":"Dotted or JSON-pointer path into the probe result (e.g. frontmost.name)"},
+                    "equals":{"description":"Match when the value at path equals this (any JSON type)"},
+                    "matches":{"type":"string","description":"Match when the value at path contains this substring"},
+                    "exists":{"type":"boolean","description":"Match when the value at path is (or isn't) present/non-null"},
+                    "timeout_ms":{"type":"integer","description":"Give up after this many ms (default 10000)"},
+                    "interval_ms":{"type":"integer","description":"Delay between polls in ms (default 250)"}
+                },
+                "required":["probe","path"],
+                "additionalProperties":false
+            }),
+        ),
     ]
 }
 
@@ -295,7 +1314,7 @@ fn client_tool(name: &str, description: &str, parameters: Value) -> Value {
     })
 }
 
-fn unix_time_nanos_now() -> u64 {
+pub(crate) fn unix_time_nanos_now() -> u64 {
     match SystemTime::now().duration_since(UNIX_EPOCH) {
         Ok(dur) => dur.as_nanos() as u64,
         Err(_) => 0,
@@ -312,6 +1331,10 @@ mod tests {
         assert_eq!(map_tool_name_to_seq_op("seq.open_app"), Some("open_app"));
         assert_eq!(map_tool_name_to_seq_op("seq:open-app"), Some("open_app"));
         assert_eq!(map_tool_name_to_seq_op("PING"), Some("ping"));
+        assert_eq!(map_tool_name_to_seq_op("seq_type_text"), Some("type_text"));
+        assert_eq!(map_tool_name_to_seq_op("seq_key_press"), Some("key_press"));
+        assert_eq!(map_tool_name_to_seq_op("seq_key_combo"), Some("key_combo"));
+        assert_eq!(map_tool_name_to_seq_op("seq_wait_for"), Some("wait_for"));
         assert_eq!(map_tool_name_to_seq_op("unknown_tool"), None);
     }
 
@@ -334,13 +1357,17 @@ mod tests {
     #[test]
     fn emits_expected_tool_catalog() {
         let defs = client_side_tool_definitions();
-        assert_eq!(defs.len(), 13);
+        assert_eq!(defs.len(), 17);
         let names: Vec<&str> = defs
             .iter()
             .filter_map(|v| v.get("name").and_then(Value::as_str))
             .collect();
         assert!(names.contains(&"seq_open_app"));
         assert!(names.contains(&"seq_screenshot"));
+        assert!(names.contains(&"seq_type_text"));
+        assert!(names.contains(&"seq_key_press"));
+        assert!(names.contains(&"seq_key_combo"));
+        assert!(names.contains(&"seq_wait_for"));
     }
 
     #[test]
@@ -374,6 +1401,7 @@ mod tests {
                     .expect_err("should error")
                     .to_string(),
             ),
+            error_detail: None,
         };
 
         assert_eq!(result.tool_call_id, "tcX");
@@ -388,4 +1416,301 @@ mod tests {
         let e = BridgeError::UnsupportedTool("foo".to_string());
         assert_eq!(e.to_string(), "unsupported seq tool name: foo");
     }
+
+    #[test]
+    fn transient_errors_are_classified_for_retry() {
+        assert!(is_transient_error("connection reset by peer"));
+        assert!(is_transient_error("request timed out after 500ms"));
+        assert!(is_transient_error("seqd: Daemon Busy, try again"));
+        assert!(!is_transient_error("unsupported seq tool name: foo"));
+        assert!(!is_transient_error("invalid args: missing name"));
+    }
+
+    #[test]
+    fn retry_backoff_delay_is_capped_and_grows_with_attempt() {
+        let policy = ToolCallRetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            multiplier: 2.0,
+            max_delay: Duration::from_millis(300),
+        };
+
+        let first = retry_backoff_delay(&policy, 1);
+        let second = retry_backoff_delay(&policy, 2);
+        let capped = retry_backoff_delay(&policy, 10);
+
+        assert!(first.as_millis() <= 120, "first attempt should be ~base_delay");
+        assert!(second.as_millis() > first.as_millis());
+        assert!(capped.as_millis() <= 360, "delay must respect max_delay plus jitter");
+    }
+
+    #[test]
+    fn build_error_detail_redacts_sensitive_args_and_splits_trace() {
+        let call = ToolCall {
+            id: "tc1".to_string(),
+            name: "seq_open_app".to_string(),
+            arguments: json!({"name": "Safari", "auth_token": "s3cr3t"}),
+        };
+
+        let detail = build_error_detail(
+            "session-1",
+            "event-1",
+            &call,
+            "open_app",
+            Some("app not found\n  at seqd::open_app\n  at seqd::dispatch"),
+        );
+
+        assert_eq!(detail.seq_op, "open_app");
+        assert_eq!(detail.request_id, "everruns:event-1:tc1");
+        assert_eq!(detail.run_id, "session-1");
+        assert_eq!(detail.args_snapshot["name"], json!("Safari"));
+        assert_eq!(detail.args_snapshot["auth_token"], json!("***redacted***"));
+        assert!(detail.daemon_error.unwrap().starts_with("app not found"));
+        assert!(detail.daemon_trace.unwrap().contains("seqd::dispatch"));
+    }
+
+    #[test]
+    fn build_error_detail_leaves_daemon_fields_empty_without_a_daemon_response() {
+        let call = ToolCall {
+            id: "tc1".to_string(),
+            name: "seq_ping".to_string(),
+            arguments: json!({}),
+        };
+
+        let detail = build_error_detail("session-1", "event-1", &call, "ping", None);
+        assert!(detail.daemon_error.is_none());
+        assert!(detail.daemon_trace.is_none());
+    }
+
+    fn tool_result(id: &str, result: Value) -> ToolResult {
+        ToolResult {
+            tool_call_id: id.to_string(),
+            result: Some(result),
+            error: None,
+            error_detail: None,
+        }
+    }
+
+    #[test]
+    fn whole_string_placeholder_resolves_to_typed_value() {
+        let prior = HashMap::from([(
+            "tc1".to_string(),
+            tool_result("tc1", json!({"bounds": {"x": 42}})),
+        )]);
+
+        let resolved =
+            resolve_placeholders(&json!("{{tc1.result.bounds.x}}"), &prior).expect("resolves");
+        assert_eq!(resolved, json!(42));
+    }
+
+    #[test]
+    fn embedded_placeholder_is_substituted_as_text() {
+        let prior = HashMap::from([(
+            "tc1".to_string(),
+            tool_result("tc1", json!({"bounds": {"x": 42}})),
+        )]);
+
+        let resolved = resolve_placeholders(&json!("x is {{tc1.result.bounds.x}} px"), &prior)
+            .expect("resolves");
+        assert_eq!(resolved, json!("x is 42 px"));
+    }
+
+    #[test]
+    fn placeholder_referencing_missing_tool_call_errors() {
+        let prior = HashMap::new();
+        let err = resolve_placeholders(&json!("{{tc1.result.x}}"), &prior).expect_err("no tc1");
+        assert!(err.contains("no prior tool result"));
+    }
+
+    #[test]
+    fn placeholder_referencing_failed_result_surfaces_error_text() {
+        let prior = HashMap::from([(
+            "tc1".to_string(),
+            ToolResult {
+                tool_call_id: "tc1".to_string(),
+                result: None,
+                error: Some("app not found".to_string()),
+                error_detail: None,
+            },
+        )]);
+
+        let resolved =
+            resolve_placeholders(&json!("{{tc1.error}}"), &prior).expect("resolves");
+        assert_eq!(resolved, json!("app not found"));
+    }
+
+    #[test]
+    fn topo_sort_layers_orders_by_placeholder_dependency() {
+        let calls = vec![
+            ToolCall {
+                id: "tc2".to_string(),
+                name: "seq_click".to_string(),
+                arguments: json!({"x": "{{tc1.result.bounds.x}}", "y": 0}),
+            },
+            ToolCall {
+                id: "tc1".to_string(),
+                name: "seq_app_state".to_string(),
+                arguments: json!({}),
+            },
+        ];
+
+        let layers = topo_sort_layers(&calls).expect("no cycle");
+        assert_eq!(layers, vec![vec!["tc1".to_string()], vec!["tc2".to_string()]]);
+    }
+
+    #[test]
+    fn topo_sort_layers_detects_cycle() {
+        let calls = vec![
+            ToolCall {
+                id: "tc1".to_string(),
+                name: "seq_click".to_string(),
+                arguments: json!({"x": "{{tc2.result.x}}"}),
+            },
+            ToolCall {
+                id: "tc2".to_string(),
+                name: "seq_click".to_string(),
+                arguments: json!({"x": "{{tc1.result.x}}"}),
+            },
+        ];
+
+        assert!(topo_sort_layers(&calls).is_err());
+    }
+
+    #[test]
+    fn blocking_deps_skips_only_actual_dependents_of_a_failure() {
+        // layer0: a (fails), b (succeeds); layer1: c depends on a, d depends on b.
+        let calls = [
+            ToolCall { id: "a".to_string(), name: "seq_ping".to_string(), arguments: json!({}) },
+            ToolCall { id: "b".to_string(), name: "seq_ping".to_string(), arguments: json!({}) },
+            ToolCall {
+                id: "c".to_string(),
+                name: "seq_ping".to_string(),
+                arguments: json!({"x": "{{a.result.x}}"}),
+            },
+            ToolCall {
+                id: "d".to_string(),
+                name: "seq_ping".to_string(),
+                arguments: json!({"x": "{{b.result.x}}"}),
+            },
+        ];
+        let known_ids: HashSet<&str> = calls.iter().map(|call| call.id.as_str()).collect();
+        let deps_by_id: HashMap<&str, HashSet<String>> = calls
+            .iter()
+            .map(|call| (call.id.as_str(), call_dependencies(call, &known_ids)))
+            .collect();
+
+        let mut failed_ids: HashSet<String> = HashSet::new();
+        failed_ids.insert("a".to_string());
+
+        assert_eq!(
+            blocking_deps("c", &deps_by_id, &failed_ids, ToolCallMode::StopOnError),
+            vec!["a"]
+        );
+        assert!(blocking_deps("d", &deps_by_id, &failed_ids, ToolCallMode::StopOnError).is_empty());
+    }
+
+    #[test]
+    fn blocking_deps_is_always_empty_for_continue_on_error() {
+        let calls = [
+            ToolCall { id: "a".to_string(), name: "seq_ping".to_string(), arguments: json!({}) },
+            ToolCall {
+                id: "c".to_string(),
+                name: "seq_ping".to_string(),
+                arguments: json!({"x": "{{a.result.x}}"}),
+            },
+        ];
+        let known_ids: HashSet<&str> = calls.iter().map(|call| call.id.as_str()).collect();
+        let deps_by_id: HashMap<&str, HashSet<String>> = calls
+            .iter()
+            .map(|call| (call.id.as_str(), call_dependencies(call, &known_ids)))
+            .collect();
+
+        let mut failed_ids: HashSet<String> = HashSet::new();
+        failed_ids.insert("a".to_string());
+
+        assert!(blocking_deps("c", &deps_by_id, &failed_ids, ToolCallMode::ContinueOnError).is_empty());
+    }
+
+    #[test]
+    fn skipped_result_message_names_the_blocking_dependency() {
+        let result = skipped_result("c", &["a"]);
+        assert_eq!(result.tool_call_id, "c");
+        assert_eq!(result.error.as_deref(), Some("skipped: upstream dependency \"a\" failed"));
+
+        let result = skipped_result("e", &["a", "b"]);
+        assert_eq!(
+            result.error.as_deref(),
+            Some("skipped: upstream dependencies \"a\", \"b\" failed")
+        );
+    }
+
+    fn wait_for_args(json_args: Value) -> WaitForArgs {
+        serde_json::from_value(json_args).expect("valid wait_for args")
+    }
+
+    #[test]
+    fn wait_for_pointer_converts_dotted_path() {
+        assert_eq!(wait_for_pointer("frontmost.name"), "/frontmost/name");
+        assert_eq!(wait_for_pointer("/already/pointer"), "/already/pointer");
+        assert_eq!(wait_for_pointer(""), "");
+    }
+
+    #[test]
+    fn wait_for_predicate_equals_matches_exact_value() {
+        let args = wait_for_args(json!({
+            "probe": "app_state",
+            "path": "frontmost.name",
+            "equals": "Safari"
+        }));
+
+        assert!(wait_for_predicate_holds(&args, &json!("Safari")).unwrap());
+        assert!(!wait_for_predicate_holds(&args, &json!("Finder")).unwrap());
+    }
+
+    #[test]
+    fn wait_for_predicate_matches_checks_substring() {
+        let args = wait_for_args(json!({
+            "probe": "perf",
+            "path": "status",
+            "matches": "ready"
+        }));
+
+        assert!(wait_for_predicate_holds(&args, &json!("daemon ready")).unwrap());
+        assert!(!wait_for_predicate_holds(&args, &json!("starting")).unwrap());
+    }
+
+    #[test]
+    fn wait_for_predicate_exists_checks_non_null() {
+        let args = wait_for_args(json!({
+            "probe": "app_state",
+            "path": "frontmost",
+            "exists": true
+        }));
+
+        assert!(wait_for_predicate_holds(&args, &json!({"name": "Safari"})).unwrap());
+        assert!(!wait_for_predicate_holds(&args, &Value::Null).unwrap());
+    }
+
+    #[test]
+    fn wait_for_predicate_requires_one_condition() {
+        let args = wait_for_args(json!({
+            "probe": "app_state",
+            "path": "frontmost"
+        }));
+
+        let err = wait_for_predicate_holds(&args, &json!("x")).unwrap_err();
+        assert!(err.contains("requires one of"));
+    }
+
+    #[test]
+    fn wait_for_args_default_timeout_and_interval() {
+        let args = wait_for_args(json!({
+            "probe": "app_state",
+            "path": "frontmost.name",
+            "equals": "Safari"
+        }));
+
+        assert_eq!(args.timeout_ms, DEFAULT_WAIT_FOR_TIMEOUT_MS);
+        assert_eq!(args.interval_ms, DEFAULT_WAIT_FOR_INTERVAL_MS);
+    }
 }
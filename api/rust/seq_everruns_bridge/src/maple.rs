@@ -1,9 +1,11 @@
+use base64::Engine as _;
 use serde_json::{json, Value};
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TryRecvError};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 const DEFAULT_SCOPE_NAME: &str = "seq_everruns_bridge";
@@ -14,6 +16,10 @@ const DEFAULT_MAX_BATCH_SIZE: usize = 128;
 const DEFAULT_FLUSH_INTERVAL_MS: u64 = 50;
 const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 400;
 const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 800;
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const DEFAULT_MAX_BACKOFF_MS: u64 = 30_000;
+const RETRY_BASE_MS: u64 = 200;
+const DEFAULT_SAMPLE_RATIO: f64 = 1.0;
 
 #[derive(Debug, Clone)]
 pub struct MapleIngestTarget {
@@ -33,6 +39,14 @@ pub struct MapleExporterConfig {
     pub connect_timeout: Duration,
     pub request_timeout: Duration,
     pub targets: Vec<MapleIngestTarget>,
+    pub transport_factory: MapleTransportFactory,
+    pub max_retries: u32,
+    pub max_backoff: Duration,
+    /// Fraction of traces to keep, in `[0.0, 1.0]`. The keep/drop decision
+    /// is made once per trace (see [`MapleSampler`]), so a sampled-in trace
+    /// keeps every one of its spans and a sampled-out trace loses all of
+    /// them — never a partial, broken-looking tree.
+    pub sample_ratio: f64,
 }
 
 impl MapleExporterConfig {
@@ -76,6 +90,14 @@ impl MapleExporterConfig {
         let request_timeout = Duration::from_millis(
             env_u64("SEQ_EVERRUNS_MAPLE_REQUEST_TIMEOUT_MS").unwrap_or(DEFAULT_REQUEST_TIMEOUT_MS),
         );
+        let max_retries =
+            env_u64("SEQ_EVERRUNS_MAPLE_MAX_RETRIES").unwrap_or(DEFAULT_MAX_RETRIES as u64) as u32;
+        let max_backoff = Duration::from_millis(
+            env_u64("SEQ_EVERRUNS_MAPLE_MAX_BACKOFF_MS").unwrap_or(DEFAULT_MAX_BACKOFF_MS),
+        );
+        let sample_ratio = env_f64("SEQ_EVERRUNS_MAPLE_SAMPLE_RATIO")
+            .unwrap_or(DEFAULT_SAMPLE_RATIO)
+            .clamp(0.0, 1.0);
 
         Ok(Some(Self {
             service_name,
@@ -88,6 +110,10 @@ impl MapleExporterConfig {
             connect_timeout,
             request_timeout,
             targets,
+            transport_factory: default_transport_factory(),
+            max_retries,
+            max_backoff,
+            sample_ratio,
         }))
     }
 }
@@ -105,10 +131,104 @@ impl Default for MapleExporterConfig {
             connect_timeout: Duration::from_millis(DEFAULT_CONNECT_TIMEOUT_MS),
             request_timeout: Duration::from_millis(DEFAULT_REQUEST_TIMEOUT_MS),
             targets: Vec::new(),
+            transport_factory: default_transport_factory(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            max_backoff: Duration::from_millis(DEFAULT_MAX_BACKOFF_MS),
+            sample_ratio: DEFAULT_SAMPLE_RATIO,
         }
     }
 }
 
+/// Abstracts the span-batch upload so the core encoding logic can compile
+/// without pulling in a concrete HTTP stack (ureq, reqwest, a mock, ...).
+pub trait MapleTransport: Send + Sync {
+    fn post(&self, endpoint: &str, ingest_key: &str, body: &[u8]) -> Result<u16, TransportError>;
+}
+
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error("transport io error: {0}")]
+    Io(String),
+    #[error("no transport implementation compiled in (enable the `ureq-transport` feature or set MapleExporterConfig::transport_factory)")]
+    Unavailable,
+}
+
+/// Builds one [`MapleTransport`] per ingest target. Wrapped so
+/// `MapleExporterConfig` can stay `Debug + Clone` despite holding a
+/// `dyn Fn`.
+#[derive(Clone)]
+#[allow(clippy::type_complexity)]
+pub struct MapleTransportFactory(Arc<dyn Fn(&MapleExporterConfig) -> Box<dyn MapleTransport> + Send + Sync>);
+
+impl MapleTransportFactory {
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: Fn(&MapleExporterConfig) -> Box<dyn MapleTransport> + Send + Sync + 'static,
+    {
+        Self(Arc::new(factory))
+    }
+
+    fn build(&self, config: &MapleExporterConfig) -> Box<dyn MapleTransport> {
+        (self.0)(config)
+    }
+}
+
+impl std::fmt::Debug for MapleTransportFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MapleTransportFactory(..)")
+    }
+}
+
+#[cfg(feature = "ureq-transport")]
+struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "ureq-transport")]
+impl MapleTransport for UreqTransport {
+    fn post(&self, endpoint: &str, ingest_key: &str, body: &[u8]) -> Result<u16, TransportError> {
+        let sent = self
+            .agent
+            .post(endpoint)
+            .set("content-type", "application/json")
+            .set("x-maple-ingest-key", ingest_key)
+            .send_bytes(body);
+        match sent {
+            Ok(resp) => Ok(resp.status()),
+            Err(ureq::Error::Status(code, _)) => Ok(code),
+            Err(err) => Err(TransportError::Io(err.to_string())),
+        }
+    }
+}
+
+#[cfg(feature = "ureq-transport")]
+fn default_transport_factory() -> MapleTransportFactory {
+    MapleTransportFactory::new(|config| {
+        Box::new(UreqTransport {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(config.connect_timeout)
+                .timeout_read(config.request_timeout)
+                .timeout_write(config.request_timeout)
+                .build(),
+        })
+    })
+}
+
+#[cfg(not(feature = "ureq-transport"))]
+struct UnavailableTransport;
+
+#[cfg(not(feature = "ureq-transport"))]
+impl MapleTransport for UnavailableTransport {
+    fn post(&self, _endpoint: &str, _ingest_key: &str, _body: &[u8]) -> Result<u16, TransportError> {
+        Err(TransportError::Unavailable)
+    }
+}
+
+#[cfg(not(feature = "ureq-transport"))]
+fn default_transport_factory() -> MapleTransportFactory {
+    MapleTransportFactory::new(|_config| Box::new(UnavailableTransport) as Box<dyn MapleTransport>)
+}
+
 #[derive(Debug, Error)]
 pub enum MapleConfigError {
     #[error("SEQ_EVERRUNS_MAPLE_TRACES_ENDPOINTS count ({endpoints}) does not match SEQ_EVERRUNS_MAPLE_INGEST_KEYS count ({keys})")]
@@ -117,6 +237,67 @@ pub enum MapleConfigError {
     IncompletePair { prefix: &'static str },
 }
 
+/// A typed OTLP attribute value. Mirrors the proto JSON mapping's `AnyValue`
+/// union so numeric/boolean attributes stay queryable instead of collapsing
+/// into strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapleAttrValue {
+    Str(String),
+    Int(i64),
+    Double(f64),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Array(Vec<MapleAttrValue>),
+}
+
+impl From<String> for MapleAttrValue {
+    fn from(value: String) -> Self {
+        MapleAttrValue::Str(value)
+    }
+}
+
+impl From<&str> for MapleAttrValue {
+    fn from(value: &str) -> Self {
+        MapleAttrValue::Str(value.to_string())
+    }
+}
+
+impl From<i64> for MapleAttrValue {
+    fn from(value: i64) -> Self {
+        MapleAttrValue::Int(value)
+    }
+}
+
+impl From<u64> for MapleAttrValue {
+    fn from(value: u64) -> Self {
+        MapleAttrValue::Int(value as i64)
+    }
+}
+
+impl From<f64> for MapleAttrValue {
+    fn from(value: f64) -> Self {
+        MapleAttrValue::Double(value)
+    }
+}
+
+impl From<bool> for MapleAttrValue {
+    fn from(value: bool) -> Self {
+        MapleAttrValue::Bool(value)
+    }
+}
+
+impl From<Vec<u8>> for MapleAttrValue {
+    fn from(value: Vec<u8>) -> Self {
+        MapleAttrValue::Bytes(value)
+    }
+}
+
+impl From<Vec<MapleAttrValue>> for MapleAttrValue {
+    fn from(value: Vec<MapleAttrValue>) -> Self {
+        MapleAttrValue::Array(value)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MapleSpan {
     pub trace_id: String,
@@ -128,7 +309,17 @@ pub struct MapleSpan {
     pub end_time_unix_nano: u64,
     pub status_code: i32,
     pub status_message: Option<String>,
-    pub attributes: Vec<(String, String)>,
+    pub attributes: Vec<(String, MapleAttrValue)>,
+    pub events: Vec<MapleSpanEvent>,
+}
+
+/// A timestamped annotation on a span, e.g. a structured error captured at
+/// the moment a tool call failed. Encoded as an OTLP span event.
+#[derive(Debug, Clone)]
+pub struct MapleSpanEvent {
+    pub time_unix_nano: u64,
+    pub name: String,
+    pub attributes: Vec<(String, MapleAttrValue)>,
 }
 
 impl MapleSpan {
@@ -140,24 +331,25 @@ impl MapleSpan {
         error: Option<&str>,
         start_time_unix_nano: u64,
         end_time_unix_nano: u64,
-        mut extra_attributes: Vec<(String, String)>,
+        mut extra_attributes: Vec<(String, MapleAttrValue)>,
+        parent_span_id: Option<&str>,
     ) -> Self {
         let trace_id = stable_trace_id(session_id, event_id);
         let span_id = stable_span_id(&format!(
             "{session_id}:{event_id}:{stage}:{start_time_unix_nano}"
         ));
-        extra_attributes.push(("session_id".to_string(), session_id.to_string()));
-        extra_attributes.push(("event_id".to_string(), event_id.to_string()));
-        extra_attributes.push(("stage".to_string(), stage.to_string()));
-        extra_attributes.push(("bridge.ok".to_string(), ok.to_string()));
+        extra_attributes.push(("session_id".to_string(), session_id.into()));
+        extra_attributes.push(("event_id".to_string(), event_id.into()));
+        extra_attributes.push(("stage".to_string(), stage.into()));
+        extra_attributes.push(("bridge.ok".to_string(), ok.into()));
         if let Some(msg) = error {
-            extra_attributes.push(("error.message".to_string(), msg.to_string()));
+            extra_attributes.push(("error.message".to_string(), msg.into()));
         }
 
         Self {
             trace_id,
             span_id,
-            parent_span_id: String::new(),
+            parent_span_id: parent_span_id.unwrap_or_default().to_string(),
             name: format!("everruns.{stage}"),
             kind: 1,
             start_time_unix_nano,
@@ -165,9 +357,11 @@ impl MapleSpan {
             status_code: if ok { 1 } else { 2 },
             status_message: error.map(|s| s.to_string()),
             attributes: extra_attributes,
+            events: Vec::new(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn for_tool_call(
         session_id: &str,
         event_id: &str,
@@ -179,29 +373,32 @@ impl MapleSpan {
         start_time_unix_nano: u64,
         end_time_unix_nano: u64,
         duration_ms: u64,
+        parent_span_id: Option<&str>,
+        attempts: u64,
     ) -> Self {
         let trace_id = stable_trace_id(session_id, event_id);
         let span_id = stable_span_id(&format!(
             "{session_id}:{event_id}:{tool_call_id}:{start_time_unix_nano}"
         ));
 
-        let mut attributes = vec![
-            ("session_id".to_string(), session_id.to_string()),
-            ("event_id".to_string(), event_id.to_string()),
-            ("tool_call_id".to_string(), tool_call_id.to_string()),
-            ("tool_name".to_string(), tool_name.to_string()),
-            ("seq_op".to_string(), seq_op.to_string()),
-            ("bridge.ok".to_string(), ok.to_string()),
-            ("bridge.duration_ms".to_string(), duration_ms.to_string()),
+        let mut attributes: Vec<(String, MapleAttrValue)> = vec![
+            ("session_id".to_string(), session_id.into()),
+            ("event_id".to_string(), event_id.into()),
+            ("tool_call_id".to_string(), tool_call_id.into()),
+            ("tool_name".to_string(), tool_name.into()),
+            ("seq_op".to_string(), seq_op.into()),
+            ("bridge.ok".to_string(), ok.into()),
+            ("bridge.duration_ms".to_string(), duration_ms.into()),
+            ("retry.attempts".to_string(), attempts.into()),
         ];
         if let Some(msg) = error {
-            attributes.push(("error.message".to_string(), msg.to_string()));
+            attributes.push(("error.message".to_string(), msg.into()));
         }
 
         Self {
             trace_id,
             span_id,
-            parent_span_id: String::new(),
+            parent_span_id: parent_span_id.unwrap_or_default().to_string(),
             name: "everruns.tool_call".to_string(),
             kind: 3,
             start_time_unix_nano,
@@ -209,6 +406,126 @@ impl MapleSpan {
             status_code: if ok { 1 } else { 2 },
             status_message: error.map(|s| s.to_string()),
             attributes,
+            events: Vec::new(),
+        }
+    }
+}
+
+/// Tracks the call stack of currently-open spans for one trace so nested
+/// work (e.g. a tool call under its owning runtime stage) gets a real
+/// `parent_span_id` instead of landing as a flat root span.
+#[derive(Clone)]
+pub struct MapleTraceContext {
+    trace_id: String,
+    stack: Arc<Mutex<Vec<String>>>,
+}
+
+impl MapleTraceContext {
+    pub fn new(session_id: &str, event_id: &str) -> Self {
+        Self {
+            trace_id: stable_trace_id(session_id, event_id),
+            stack: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// The span id a new child would currently be nested under, or an empty
+    /// string if nothing is open (i.e. the child would be a root span).
+    pub fn current_parent_span_id(&self) -> String {
+        self.stack
+            .lock()
+            .expect("trace context stack mutex poisoned")
+            .last()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Opens a child span nested under whatever is currently on top of the
+    /// stack. `span_id_seed` feeds [`stable_span_id`] so ids stay
+    /// deterministic across retries, matching the flat constructors.
+    pub fn start_child(&self, span_id_seed: &str, name: impl Into<String>, kind: i32) -> SpanGuard {
+        let span_id = stable_span_id(span_id_seed);
+        let parent_span_id = self.current_parent_span_id();
+        self.stack
+            .lock()
+            .expect("trace context stack mutex poisoned")
+            .push(span_id.clone());
+
+        SpanGuard {
+            context: self.clone(),
+            finished: false,
+            span: MapleSpan {
+                trace_id: self.trace_id.clone(),
+                span_id,
+                parent_span_id,
+                name: name.into(),
+                kind,
+                start_time_unix_nano: crate::unix_time_nanos_now(),
+                end_time_unix_nano: 0,
+                status_code: 0,
+                status_message: None,
+                attributes: Vec::new(),
+                events: Vec::new(),
+            },
+        }
+    }
+}
+
+/// A span opened via [`MapleTraceContext::start_child`]. Pops itself off the
+/// context's active-span stack and stamps `end_time_unix_nano` either when
+/// [`SpanGuard::end`] is called explicitly or, if the caller forgets, when
+/// the guard is dropped.
+pub struct SpanGuard {
+    context: MapleTraceContext,
+    finished: bool,
+    span: MapleSpan,
+}
+
+impl SpanGuard {
+    pub fn set_status(&mut self, ok: bool, message: Option<&str>) {
+        self.span.status_code = if ok { 1 } else { 2 };
+        self.span.status_message = message.map(|s| s.to_string());
+    }
+
+    pub fn set_attribute(&mut self, key: impl Into<String>, value: impl Into<MapleAttrValue>) {
+        self.span.attributes.push((key.into(), value.into()));
+    }
+
+    pub fn span_id(&self) -> &str {
+        &self.span.span_id
+    }
+
+    /// Finish the span now and return it for emission, popping it off the
+    /// trace context's active-span stack.
+    pub fn end(mut self) -> MapleSpan {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> MapleSpan {
+        if !self.finished {
+            self.span.end_time_unix_nano = crate::unix_time_nanos_now();
+            self.finished = true;
+
+            let mut stack = self
+                .context
+                .stack
+                .lock()
+                .expect("trace context stack mutex poisoned");
+            if stack.last() == Some(&self.span.span_id) {
+                stack.pop();
+            }
+        }
+        self.span.clone()
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish();
         }
     }
 }
@@ -219,6 +536,9 @@ pub struct MapleExporterStats {
     pub sent: u64,
     pub failed: u64,
     pub dropped: u64,
+    pub retry_queued: u64,
+    pub retried: u64,
+    pub sampled_out: u64,
 }
 
 #[derive(Default)]
@@ -227,17 +547,67 @@ struct MapleExporterStatsAtomic {
     sent: AtomicU64,
     failed: AtomicU64,
     dropped: AtomicU64,
+    retry_queued: AtomicU64,
+    retried: AtomicU64,
+    sampled_out: AtomicU64,
+}
+
+/// Head-based sampler that makes one keep/drop decision per trace (by
+/// hashing `trace_id`), so a kept trace always renders as a complete tree
+/// and a dropped one never shows up as a partial, broken one.
+///
+/// This deliberately does *not* special-case error spans: the decision for
+/// a trace is made the moment its first span is seen, long before anyone
+/// knows whether a later span in that same trace will fail. Keeping a span
+/// just because it errored would export it without its already-dropped
+/// parents and siblings — exactly the partial, broken-looking tree this
+/// sampler exists to avoid. Guaranteeing every error stays visible needs
+/// tail-based sampling (buffer a trace until it finishes, then decide),
+/// which is a different exporter shape than this one.
+#[derive(Debug, Clone, Copy)]
+pub struct MapleSampler {
+    ratio: f64,
+}
+
+impl MapleSampler {
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio: ratio.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn should_keep(&self, span: &MapleSpan) -> bool {
+        if self.ratio >= 1.0 {
+            return true;
+        }
+        if self.ratio <= 0.0 {
+            return false;
+        }
+
+        let hash = fnv1a64(span.trace_id.as_bytes());
+        (hash as f64 / u64::MAX as f64) < self.ratio
+    }
+}
+
+/// A batch that failed delivery to one target and is waiting for its next
+/// backed-off redelivery attempt.
+struct RetryBatch {
+    spans: Vec<MapleSpan>,
+    attempt: u32,
+    next_attempt_at: Instant,
 }
 
 struct WorkerTarget {
     traces_endpoint: String,
     ingest_key: String,
-    agent: ureq::Agent,
+    transport: Box<dyn MapleTransport>,
+    retry_queue: VecDeque<RetryBatch>,
 }
 
 pub struct MapleTraceExporter {
     tx: SyncSender<MapleSpan>,
     stats: Arc<MapleExporterStatsAtomic>,
+    sampler: MapleSampler,
 }
 
 impl MapleTraceExporter {
@@ -249,14 +619,20 @@ impl MapleTraceExporter {
     }
 
     pub fn new(config: MapleExporterConfig) -> Self {
+        let sampler = MapleSampler::new(config.sample_ratio);
         let (tx, rx) = sync_channel(config.queue_capacity.max(1));
         let stats = Arc::new(MapleExporterStatsAtomic::default());
         let worker_stats = Arc::clone(&stats);
         thread::spawn(move || worker_main(rx, config, worker_stats));
-        Self { tx, stats }
+        Self { tx, stats, sampler }
     }
 
     pub fn emit_span(&self, span: MapleSpan) {
+        if !self.sampler.should_keep(&span) {
+            self.stats.sampled_out.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
         if self.tx.try_send(span).is_ok() {
             self.stats.enqueued.fetch_add(1, Ordering::Relaxed);
         } else {
@@ -270,6 +646,9 @@ impl MapleTraceExporter {
             sent: self.stats.sent.load(Ordering::Relaxed),
             failed: self.stats.failed.load(Ordering::Relaxed),
             dropped: self.stats.dropped.load(Ordering::Relaxed),
+            retry_queued: self.stats.retry_queued.load(Ordering::Relaxed),
+            retried: self.stats.retried.load(Ordering::Relaxed),
+            sampled_out: self.stats.sampled_out.load(Ordering::Relaxed),
         }
     }
 }
@@ -279,17 +658,15 @@ fn worker_main(
     config: MapleExporterConfig,
     stats: Arc<MapleExporterStatsAtomic>,
 ) {
-    let worker_targets: Vec<WorkerTarget> = config
+    let retry_capacity = (config.queue_capacity / config.max_batch_size.max(1)).max(1);
+    let mut worker_targets: Vec<WorkerTarget> = config
         .targets
         .iter()
         .map(|target| WorkerTarget {
             traces_endpoint: target.traces_endpoint.clone(),
             ingest_key: target.ingest_key.clone(),
-            agent: ureq::AgentBuilder::new()
-                .timeout_connect(config.connect_timeout)
-                .timeout_read(config.request_timeout)
-                .timeout_write(config.request_timeout)
-                .build(),
+            transport: config.transport_factory.build(&config),
+            retry_queue: VecDeque::new(),
         })
         .collect();
 
@@ -297,7 +674,8 @@ fn worker_main(
     let mut disconnected = false;
 
     while !disconnected {
-        match rx.recv_timeout(config.flush_interval) {
+        let recv_timeout = next_recv_timeout(config.flush_interval, &worker_targets);
+        match rx.recv_timeout(recv_timeout) {
             Ok(span) => batch.push(span),
             Err(RecvTimeoutError::Timeout) => {}
             Err(RecvTimeoutError::Disconnected) => {
@@ -317,18 +695,150 @@ fn worker_main(
         }
 
         if !batch.is_empty() {
-            flush_batch(&config, &worker_targets, &batch, &stats);
+            flush_batch(&config, &mut worker_targets, &batch, &stats, retry_capacity);
             batch.clear();
         }
+
+        process_retries(&config, &mut worker_targets, &stats, retry_capacity);
     }
 }
 
+fn next_recv_timeout(flush_interval: Duration, worker_targets: &[WorkerTarget]) -> Duration {
+    let now = Instant::now();
+    worker_targets
+        .iter()
+        .filter_map(|target| target.retry_queue.front())
+        .map(|entry| entry.next_attempt_at.saturating_duration_since(now))
+        .fold(flush_interval, Duration::min)
+}
+
 fn flush_batch(
     config: &MapleExporterConfig,
-    worker_targets: &[WorkerTarget],
+    worker_targets: &mut [WorkerTarget],
     spans: &[MapleSpan],
     stats: &Arc<MapleExporterStatsAtomic>,
+    retry_capacity: usize,
+) {
+    let body = encode_batch(config, spans);
+
+    for target in worker_targets.iter_mut() {
+        let sent = target
+            .transport
+            .post(&target.traces_endpoint, &target.ingest_key, body.as_bytes());
+
+        match sent {
+            Ok(status) if (200..300).contains(&status) => {
+                stats.sent.fetch_add(spans.len() as u64, Ordering::Relaxed);
+            }
+            Ok(_) | Err(_) => {
+                stats
+                    .failed
+                    .fetch_add(spans.len() as u64, Ordering::Relaxed);
+                enqueue_retry(
+                    target,
+                    RetryBatch {
+                        spans: spans.to_vec(),
+                        attempt: 0,
+                        next_attempt_at: Instant::now() + backoff_duration(config, 0),
+                    },
+                    retry_capacity,
+                    stats,
+                );
+            }
+        }
+    }
+}
+
+/// Drains and retries whichever per-target batches have reached their
+/// `next_attempt_at`, re-queueing (with a bigger backoff) or dropping them
+/// per `max_retries`.
+fn process_retries(
+    config: &MapleExporterConfig,
+    worker_targets: &mut [WorkerTarget],
+    stats: &Arc<MapleExporterStatsAtomic>,
+    retry_capacity: usize,
+) {
+    let now = Instant::now();
+    for target in worker_targets.iter_mut() {
+        let mut ready = Vec::new();
+        while matches!(target.retry_queue.front(), Some(entry) if entry.next_attempt_at <= now) {
+            ready.push(target.retry_queue.pop_front().expect("front checked above"));
+        }
+
+        for mut entry in ready {
+            stats.retried.fetch_add(1, Ordering::Relaxed);
+            let body = encode_batch(config, &entry.spans);
+            let sent = target
+                .transport
+                .post(&target.traces_endpoint, &target.ingest_key, body.as_bytes());
+
+            match sent {
+                Ok(status) if (200..300).contains(&status) => {
+                    stats
+                        .sent
+                        .fetch_add(entry.spans.len() as u64, Ordering::Relaxed);
+                }
+                Ok(_) | Err(_) => {
+                    entry.attempt += 1;
+                    if entry.attempt >= config.max_retries {
+                        stats
+                            .dropped
+                            .fetch_add(entry.spans.len() as u64, Ordering::Relaxed);
+                    } else {
+                        entry.next_attempt_at = now + backoff_duration(config, entry.attempt);
+                        enqueue_retry(target, entry, retry_capacity, stats);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn enqueue_retry(
+    target: &mut WorkerTarget,
+    entry: RetryBatch,
+    retry_capacity: usize,
+    stats: &Arc<MapleExporterStatsAtomic>,
 ) {
+    if target.retry_queue.len() >= retry_capacity {
+        stats
+            .dropped
+            .fetch_add(entry.spans.len() as u64, Ordering::Relaxed);
+        return;
+    }
+
+    let insert_at = target
+        .retry_queue
+        .iter()
+        .position(|queued| queued.next_attempt_at > entry.next_attempt_at)
+        .unwrap_or(target.retry_queue.len());
+    target.retry_queue.insert(insert_at, entry);
+    stats.retry_queued.fetch_add(1, Ordering::Relaxed);
+}
+
+/// `base * 2^attempt`, capped at `max_backoff`, with up to ±20% jitter so
+/// multiple targets retrying in lockstep don't thunder-herd the endpoint.
+fn backoff_duration(config: &MapleExporterConfig, attempt: u32) -> Duration {
+    let exp_ms = RETRY_BASE_MS.saturating_mul(1u64 << attempt.min(24));
+    let capped_ms = exp_ms.min(config.max_backoff.as_millis() as u64);
+    let jitter = jitter_fraction(attempt as u64 ^ capped_ms);
+    let jittered_ms = (capped_ms as f64 * (1.0 + jitter)).max(0.0) as u64;
+    Duration::from_millis(jittered_ms)
+}
+
+/// Deterministic-looking jitter in `[-0.2, 0.2]` derived from the current
+/// time and `seed`, without pulling in a `rand` dependency.
+fn jitter_fraction(seed: u64) -> f64 {
+    let now_nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let hashed = fnv1a64(&(seed ^ now_nanos).to_le_bytes());
+    let normalized = (hashed % 1000) as f64 / 1000.0;
+    (normalized - 0.5) * 0.4
+}
+
+fn encode_batch(config: &MapleExporterConfig, spans: &[MapleSpan]) -> String {
     let spans_payload: Vec<Value> = spans.iter().map(encode_span).collect();
     let resource_attrs = build_resource_attrs(config);
     let payload = json!({
@@ -346,27 +856,7 @@ fn flush_batch(
             }
         ]
     });
-
-    let body = payload.to_string();
-    for target in worker_targets {
-        let sent = target
-            .agent
-            .post(&target.traces_endpoint)
-            .set("content-type", "application/json")
-            .set("x-maple-ingest-key", &target.ingest_key)
-            .send_string(&body);
-
-        match sent {
-            Ok(resp) if (200..300).contains(&resp.status()) => {
-                stats.sent.fetch_add(spans.len() as u64, Ordering::Relaxed);
-            }
-            Ok(_) | Err(_) => {
-                stats
-                    .failed
-                    .fetch_add(spans.len() as u64, Ordering::Relaxed);
-            }
-        }
-    }
+    payload.to_string()
 }
 
 fn build_resource_attrs(config: &MapleExporterConfig) -> Vec<Value> {
@@ -394,8 +884,9 @@ fn encode_span(span: &MapleSpan) -> Value {
         "attributes": span
             .attributes
             .iter()
-            .map(|(key, value)| otlp_string_attr(key, value))
+            .map(|(key, value)| otlp_attr(key, value))
             .collect::<Vec<Value>>(),
+        "events": span.events.iter().map(encode_span_event).collect::<Vec<Value>>(),
         "status": {
             "code": span.status_code,
             "message": span.status_message.clone().unwrap_or_default()
@@ -403,6 +894,18 @@ fn encode_span(span: &MapleSpan) -> Value {
     })
 }
 
+fn encode_span_event(event: &MapleSpanEvent) -> Value {
+    json!({
+        "timeUnixNano": event.time_unix_nano.to_string(),
+        "name": event.name,
+        "attributes": event
+            .attributes
+            .iter()
+            .map(|(key, value)| otlp_attr(key, value))
+            .collect::<Vec<Value>>()
+    })
+}
+
 fn otlp_string_attr(key: &str, value: &str) -> Value {
     json!({
         "key": key,
@@ -410,6 +913,32 @@ fn otlp_string_attr(key: &str, value: &str) -> Value {
     })
 }
 
+fn otlp_attr(key: &str, value: &MapleAttrValue) -> Value {
+    json!({
+        "key": key,
+        "value": otlp_attr_value(value)
+    })
+}
+
+fn otlp_attr_value(value: &MapleAttrValue) -> Value {
+    match value {
+        MapleAttrValue::Str(s) => json!({ "stringValue": s }),
+        // OTLP's proto-JSON mapping renders int64 as a decimal string so it
+        // survives round-tripping through JSON number precision limits.
+        MapleAttrValue::Int(i) => json!({ "intValue": i.to_string() }),
+        MapleAttrValue::Double(d) => json!({ "doubleValue": d }),
+        MapleAttrValue::Bool(b) => json!({ "boolValue": b }),
+        MapleAttrValue::Bytes(bytes) => json!({
+            "bytesValue": base64::engine::general_purpose::STANDARD.encode(bytes)
+        }),
+        MapleAttrValue::Array(items) => json!({
+            "arrayValue": {
+                "values": items.iter().map(otlp_attr_value).collect::<Vec<Value>>()
+            }
+        }),
+    }
+}
+
 fn parse_targets_from_env() -> Result<Vec<MapleIngestTarget>, MapleConfigError> {
     let mut targets = Vec::new();
 
@@ -504,6 +1033,12 @@ fn env_u64(key: &str) -> Option<u64> {
         .and_then(|v| v.trim().parse::<u64>().ok())
 }
 
+fn env_f64(key: &str) -> Option<f64> {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse::<f64>().ok())
+}
+
 fn non_empty(s: impl AsRef<str>) -> Option<String> {
     let value = s.as_ref().trim();
     if value.is_empty() {
@@ -559,6 +1094,9 @@ mod tests {
             "SEQ_EVERRUNS_MAPLE_FLUSH_INTERVAL_MS",
             "SEQ_EVERRUNS_MAPLE_CONNECT_TIMEOUT_MS",
             "SEQ_EVERRUNS_MAPLE_REQUEST_TIMEOUT_MS",
+            "SEQ_EVERRUNS_MAPLE_MAX_RETRIES",
+            "SEQ_EVERRUNS_MAPLE_MAX_BACKOFF_MS",
+            "SEQ_EVERRUNS_MAPLE_SAMPLE_RATIO",
         ];
         for key in keys {
             std::env::remove_var(key);
@@ -670,6 +1208,10 @@ mod tests {
                 traces_endpoint: format!("http://{addr}/v1/traces"),
                 ingest_key: "maple_pk_test".to_string(),
             }],
+            transport_factory: default_transport_factory(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            max_backoff: Duration::from_millis(DEFAULT_MAX_BACKOFF_MS),
+            sample_ratio: DEFAULT_SAMPLE_RATIO,
         };
 
         let exporter = MapleTraceExporter::new(config);
@@ -684,6 +1226,8 @@ mod tests {
             1_739_890_000_000_000_000,
             1_739_890_000_100_000_000,
             100,
+            None,
+            1,
         );
         exporter.emit_span(span);
 
@@ -692,4 +1236,122 @@ mod tests {
         assert!(stats.sent >= 1, "expected at least one sent span");
         let _ = server.join();
     }
+
+    #[test]
+    fn backoff_duration_is_capped_and_jittered() {
+        let config = MapleExporterConfig {
+            max_backoff: Duration::from_millis(1_000),
+            ..MapleExporterConfig::default()
+        };
+        for attempt in 0..10 {
+            let backoff = backoff_duration(&config, attempt);
+            assert!(backoff.as_millis() <= (1_000 * 12 / 10) as u128);
+        }
+    }
+
+    struct AlwaysFailTransport;
+
+    impl MapleTransport for AlwaysFailTransport {
+        fn post(&self, _endpoint: &str, _ingest_key: &str, _body: &[u8]) -> Result<u16, TransportError> {
+            Err(TransportError::Io("connection refused".to_string()))
+        }
+    }
+
+    #[test]
+    fn failed_delivery_is_queued_for_retry_and_eventually_dropped() {
+        let config = MapleExporterConfig {
+            max_retries: 2,
+            max_backoff: Duration::from_millis(1),
+            targets: vec![MapleIngestTarget {
+                traces_endpoint: "http://127.0.0.1:1/v1/traces".to_string(),
+                ingest_key: "maple_pk_test".to_string(),
+            }],
+            transport_factory: MapleTransportFactory::new(|_config| Box::new(AlwaysFailTransport)),
+            ..MapleExporterConfig::default()
+        };
+
+        let mut worker_targets = vec![WorkerTarget {
+            traces_endpoint: config.targets[0].traces_endpoint.clone(),
+            ingest_key: config.targets[0].ingest_key.clone(),
+            transport: config.transport_factory.build(&config),
+            retry_queue: VecDeque::new(),
+        }];
+        let stats = Arc::new(MapleExporterStatsAtomic::default());
+        let span = MapleSpan::for_tool_call(
+            "session-1", "event-1", "tool-1", "seq_ping", "ping", true, None, 0, 1, 1, None, 1,
+        );
+
+        flush_batch(&config, &mut worker_targets, &[span], &stats, 8);
+        assert_eq!(stats.retry_queued.load(Ordering::Relaxed), 1);
+
+        for _ in 0..config.max_retries {
+            std::thread::sleep(Duration::from_millis(5));
+            process_retries(&config, &mut worker_targets, &stats, 8);
+        }
+
+        assert!(worker_targets[0].retry_queue.is_empty());
+        assert_eq!(stats.dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(stats.retried.load(Ordering::Relaxed), config.max_retries as u64);
+    }
+
+    #[test]
+    fn nested_spans_get_real_parent_ids() {
+        let ctx = MapleTraceContext::new("session-1", "event-1");
+        assert_eq!(ctx.current_parent_span_id(), "");
+
+        let stage_guard = ctx.start_child("session-1:event-1:stage", "everruns.tool_stage", 1);
+        let stage_span_id = stage_guard.span_id().to_string();
+        assert_eq!(ctx.current_parent_span_id(), stage_span_id);
+
+        let tool_guard = ctx.start_child("session-1:event-1:tool-1", "everruns.tool_call", 3);
+        let tool_span = tool_guard.end();
+        assert_eq!(tool_span.parent_span_id, stage_span_id);
+        assert_eq!(ctx.current_parent_span_id(), stage_span_id);
+
+        let stage_span = stage_guard.end();
+        assert_eq!(stage_span.parent_span_id, "");
+        assert_eq!(ctx.current_parent_span_id(), "");
+    }
+
+    #[test]
+    fn sampler_decision_is_consistent_per_trace() {
+        let sampler = MapleSampler::new(0.5);
+        let mut span = MapleSpan::for_tool_call(
+            "session-1", "event-1", "tool-1", "seq_ping", "ping", true, None, 0, 1, 1, None, 1,
+        );
+        let first = sampler.should_keep(&span);
+        for _ in 0..10 {
+            assert_eq!(sampler.should_keep(&span), first);
+        }
+
+        // The decision is keyed on `trace_id` alone, not `status_code`: an
+        // error span in the same trace gets the exact same verdict as its
+        // non-error siblings, so the trace is either kept whole or dropped
+        // whole — never a partial tree missing the spans that errored.
+        span.status_code = 2;
+        assert_eq!(
+            sampler.should_keep(&span),
+            first,
+            "status_code must not change the verdict for the same trace_id"
+        );
+    }
+
+    #[test]
+    fn sampler_ratio_zero_and_one_are_absolute() {
+        let span = MapleSpan::for_tool_call(
+            "session-1", "event-1", "tool-1", "seq_ping", "ping", true, None, 0, 1, 1, None, 1,
+        );
+        assert!(!MapleSampler::new(0.0).should_keep(&span));
+        assert!(MapleSampler::new(1.0).should_keep(&span));
+    }
+
+    #[test]
+    fn dropped_span_guard_still_closes_and_pops() {
+        let ctx = MapleTraceContext::new("session-1", "event-1");
+        {
+            let _guard = ctx.start_child("session-1:event-1:stage", "everruns.tool_stage", 1);
+            assert_ne!(ctx.current_parent_span_id(), "");
+        }
+        assert_eq!(ctx.current_parent_span_id(), "");
+    }
 }